@@ -0,0 +1,42 @@
+#ifndef PROFILING_H
+#define PROFILING_H
+
+/* Generated by cbindgen from profiling-capi/src/lib.rs. Don't edit this file directly. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Begins a scope that lasts until the matching `profiling_scope_end` on the same thread.
+//
+// # Safety
+//
+// `name` must be a null-terminated UTF-8 string that stays valid for the rest of the process,
+// i.e. a string literal.
+void profiling_scope_begin(const char *name);
+
+// Ends the scope most recently begun with `profiling_scope_begin` on this thread. In debug
+// builds, ending a scope that was never begun aborts.
+void profiling_scope_end(void);
+
+// Names the current thread in the profiler. Pass null to use the thread's own name.
+//
+// # Safety
+//
+// `name` must be null or a null-terminated UTF-8 string. It only needs to be valid for the
+// duration of the call.
+void profiling_register_thread(const char *name);
+
+// Marks the end of a frame
+void profiling_finish_frame(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PROFILING_H */
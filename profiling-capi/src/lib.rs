@@ -0,0 +1,126 @@
+//! C bindings for `profiling`. C and C++ code calling these functions records into whichever
+//! backends this crate was built with, so their scopes end up in the same trace as the Rust ones.
+//!
+//! The declarations are in `include/profiling.h`. When the process also contains Rust code, depend
+//! on this crate from the Rust binary so the C functions share its copy of `profiling`, rather
+//! than linking the static or dynamic library separately.
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+/// Begins a scope that lasts until the matching `profiling_scope_end` on the same thread.
+///
+/// # Safety
+///
+/// `name` must be a null-terminated UTF-8 string that stays valid for the rest of the process,
+/// i.e. a string literal.
+#[no_mangle]
+pub unsafe extern "C" fn profiling_scope_begin(name: *const c_char) {
+    let name = static_str(name);
+    abort_on_panic(|| {
+        profiling::begin_scope(name);
+    });
+}
+
+/// Ends the scope most recently begun with `profiling_scope_begin` on this thread. In debug
+/// builds, ending a scope that was never begun aborts the process.
+#[no_mangle]
+pub extern "C" fn profiling_scope_end() {
+    abort_on_panic(profiling::end_scope);
+}
+
+/// Names the current thread in the profiler. Pass null to use the thread's own name.
+///
+/// # Safety
+///
+/// `name` must be null or a null-terminated UTF-8 string. It only needs to be valid for the
+/// duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn profiling_register_thread(name: *const c_char) {
+    if name.is_null() {
+        abort_on_panic(|| {
+            profiling::register_thread!();
+        });
+    } else {
+        abort_on_panic(|| {
+            profiling::register_thread!(to_str(name));
+        });
+    }
+}
+
+/// Marks the end of a frame
+#[no_mangle]
+pub extern "C" fn profiling_finish_frame() {
+    abort_on_panic(|| {
+        profiling::finish_frame!();
+    });
+}
+
+// Unwinding out of an extern "C" function is undefined behavior on older compilers, so a panic,
+// such as the debug check for an unmatched end, aborts explicitly instead
+fn abort_on_panic(f: impl FnOnce() + std::panic::UnwindSafe) {
+    if std::panic::catch_unwind(f).is_err() {
+        std::process::abort();
+    }
+}
+
+// Backends that need static names keep them for the rest of the process
+unsafe fn static_str(s: *const c_char) -> &'static str {
+    if s.is_null() {
+        ""
+    } else {
+        to_str(s)
+    }
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> &'a str {
+    CStr::from_ptr(s).to_str().unwrap_or("<invalid UTF-8>")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::{Command, Stdio};
+
+    const UNMATCHED_END: &str = "PROFILING_CAPI_TEST_UNMATCHED_END";
+
+    // Aborting would take the test harness down with it, so the unmatched end happens in a child
+    // process that runs only this test
+    #[test]
+    fn unmatched_scope_end() {
+        if std::env::var_os(UNMATCHED_END).is_some() {
+            super::profiling_scope_end();
+            return;
+        }
+
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["tests::unmatched_scope_end", "--exact"])
+            .env(UNMATCHED_END, "1")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+
+        // Nesting is only checked in debug builds with a backend, otherwise the end is ignored
+        let checked = cfg!(all(
+            debug_assertions,
+            any(
+                feature = "profile-with-puffin",
+                feature = "profile-with-optick",
+                feature = "profile-with-superluminal",
+                feature = "profile-with-tracing",
+                feature = "profile-with-tracy",
+                feature = "profile-with-custom",
+                feature = "profile-with-builtin",
+                feature = "type-check"
+            )
+        ));
+        assert_eq!(status.success(), !checked);
+
+        #[cfg(unix)]
+        if checked {
+            use std::os::unix::process::ExitStatusExt;
+            // SIGABRT
+            assert_eq!(status.signal(), Some(6));
+        }
+    }
+}
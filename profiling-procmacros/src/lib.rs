@@ -5,16 +5,37 @@ use syn::{parse_macro_input, parse_quote, ImplItem, ItemFn, ItemImpl};
 
 #[proc_macro_attribute]
 pub fn function(
-    _attr: TokenStream,
+    attr: TokenStream,
     item: TokenStream,
 ) -> TokenStream {
+    // Accepts an optional `color = 0xRRGGBB`
+    let mut color: Option<syn::Expr> = None;
+    let attr_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("color") {
+            color = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported profiling::function argument"))
+        }
+    });
+    parse_macro_input!(attr with attr_parser);
+
     let mut function = parse_macro_input!(item as ItemFn);
     let instrumented_function_name = function.sig.ident.to_string();
 
     let body = &function.block;
-    let new_body: syn::Block = impl_block(body, &instrumented_function_name);
+    let new_body: syn::Block = if function.sig.asyncness.is_some() {
+        impl_async_block(
+            body,
+            &instrumented_function_name,
+            color.as_ref(),
+            &function.sig.output,
+        )
+    } else {
+        impl_block(body, &instrumented_function_name, color.as_ref())
+    };
 
-    function.block = Box::new(new_body);
+    *function.block = new_body;
 
     (quote! {
         #function
@@ -78,7 +99,11 @@ pub fn all_functions(
         }
         let prev_block = &func.block;
         let calling_info = format!("{}: {}", struct_name, func.sig.ident);
-        func.block = impl_block(prev_block, &calling_info);
+        func.block = if func.sig.asyncness.is_some() {
+            impl_async_block(prev_block, &calling_info, None, &func.sig.output)
+        } else {
+            impl_block(prev_block, &calling_info, None)
+        };
     }
 
     (quote!(
@@ -87,54 +112,126 @@ pub fn all_functions(
     .into()
 }
 
-#[cfg(not(any(
-    feature = "profile-with-puffin",
-    feature = "profile-with-optick",
-    feature = "profile-with-superluminal",
-    feature = "profile-with-tracing",
-    feature = "profile-with-tracy"
-)))]
+// Emits one scope per enabled backend, mirroring how the profiling macros fan out when several
+// backends are enabled at the same time
 fn impl_block(
     body: &syn::Block,
-    _instrumented_function_name: &str,
+    instrumented_function_name: &str,
+    color: Option<&syn::Expr>,
 ) -> syn::Block {
-    parse_quote! {
-        {
-            #body
-        }
+    let mut scopes: Vec<syn::Stmt> = Vec::new();
+
+    let (args, tracing_fields) = match color {
+        Some(color) => (quote!(color = #color), quote!(, color = #color)),
+        None => (quote!(), quote!()),
+    };
+
+    if cfg!(feature = "profile-with-puffin") {
+        scopes.push(parse_quote! {
+            let _puffin_fn_scope = profiling::__puffin_function_scope!(#args);
+        });
+    }
+
+    if cfg!(feature = "profile-with-optick") {
+        scopes.push(parse_quote! {
+            let _optick_fn_scope = profiling::__optick_function_scope!(#args);
+        });
+    }
+
+    if cfg!(feature = "profile-with-superluminal") {
+        scopes.push(parse_quote! {
+            let _superluminal_fn_scope = profiling::__superluminal_function_scope!(#args);
+        });
+    }
+
+    if cfg!(feature = "profile-with-tracing") {
+        scopes.push(parse_quote! {
+            let _fn_span_entered = if profiling::is_backend_enabled(profiling::Backend::Tracing) {
+                profiling::tracing::span!(profiling::tracing::Level::INFO, #instrumented_function_name #tracing_fields)
+            } else {
+                profiling::tracing::Span::none()
+            }
+            .entered();
+        });
+    }
+
+    if cfg!(feature = "profile-with-tracy") {
+        scopes.push(parse_quote! {
+            let _tracy_fn_scope = profiling::__tracy_function_scope!(#args);
+        });
+    }
+
+    if cfg!(feature = "profile-with-custom") {
+        scopes.push(parse_quote! {
+            let _custom_fn_scope = profiling::__custom_function_scope!(#args);
+        });
+    }
+
+    if cfg!(feature = "profile-with-builtin") {
+        scopes.push(parse_quote! {
+            let _builtin_fn_scope = profiling::__builtin_function_scope!(#args);
+        });
     }
-}
 
-#[cfg(any(
-    feature = "profile-with-puffin",
-    feature = "profile-with-optick",
-    feature = "profile-with-superluminal",
-    feature = "profile-with-tracy"
-))]
-fn impl_block(
-    body: &syn::Block,
-    _instrumented_function_name: &str,
-) -> syn::Block {
     parse_quote! {
         {
-            profiling::function_scope!();
+            #(#scopes)*
 
             #body
         }
     }
 }
 
-#[cfg(feature = "profile-with-tracing")]
-fn impl_block(
+// A scope held in the body of an async fn would stay open across every .await, so the body is
+// moved into an async block and each poll of it opens the same literal-named scope as scope!
+fn impl_async_block(
     body: &syn::Block,
     instrumented_function_name: &str,
+    color: Option<&syn::Expr>,
+    output: &syn::ReturnType,
 ) -> syn::Block {
+    if !cfg!(any(
+        feature = "profile-with-puffin",
+        feature = "profile-with-optick",
+        feature = "profile-with-superluminal",
+        feature = "profile-with-tracing",
+        feature = "profile-with-tracy",
+        feature = "profile-with-custom",
+        feature = "profile-with-builtin"
+    )) {
+        return body.clone();
+    }
+
+    // Annotating the result helps inference of the block's type, i.e. for `?` inside the body.
+    // This isn't possible for `impl Trait` return types.
+    let inner: syn::Block = match output {
+        syn::ReturnType::Type(_, ty) if !contains_impl_trait(ty.to_token_stream()) => {
+            parse_quote! {
+                {
+                    let __profiling_result: #ty = #body;
+                    __profiling_result
+                }
+            }
+        }
+        _ => body.clone(),
+    };
+
+    let args = match color {
+        Some(color) => quote!(#instrumented_function_name, color = #color),
+        None => quote!(#instrumented_function_name),
+    };
+
     parse_quote! {
         {
-            let _fn_span = profiling::tracing::span!(profiling::tracing::Level::INFO, #instrumented_function_name);
-            let _fn_span_entered = _fn_span.enter();
-
-            #body
+            profiling::__instrument_with(async move #inner, || profiling::scope_guard!(#args)).await
         }
     }
 }
+
+fn contains_impl_trait(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "impl",
+        proc_macro2::TokenTree::Group(group) => contains_impl_trait(group.stream()),
+        _ => false,
+    })
+}
@@ -27,7 +27,8 @@ fn some_function() {
     burn_time(5);
 }
 
-#[profiling::function]
+// Scopes can be given a color in the form 0xRRGGBB
+#[profiling::function(color = 0x1565C0)]
 fn some_inner_function(_iteration_index: usize) {
     burn_time(10);
 }
@@ -42,6 +43,17 @@ fn function_scope_function_with_data(_iteration_index: usize) {
     burn_time(5);
 }
 
+// Scopes can carry typed fields instead of a single data string
+fn fields_function(_iteration_index: usize) {
+    profiling::scope!(
+        "fields_function",
+        iteration = _iteration_index,
+        even = _iteration_index % 2 == 0,
+        label = "fields"
+    );
+    burn_time(1);
+}
+
 //
 // Example of multiple scopes in a single function
 //
@@ -50,12 +62,10 @@ fn some_other_function(iterations: usize) {
     burn_time(5);
 
     {
-        profiling::scope!("do iterations");
+        profiling::scope!("do iterations", color = 0x2E7D32);
         for i in 0..iterations {
-            profiling::scope!(
-                "some_inner_function_that_sleeps",
-                format!("other data {}", i).as_str()
-            );
+            // The data is only formatted if a backend records it
+            profiling::scope!("some_inner_function_that_sleeps", "other data {}", i);
 
             // Mixing general profiling API calls with profiler-specific API calls is allowed
             #[cfg(feature = "profile-with-optick")]
@@ -64,6 +74,9 @@ fn some_other_function(iterations: usize) {
             some_inner_function(i);
             burn_time(1);
         }
+
+        profiling::plot!("iterations", iterations);
+        profiling::message!("iterations done", 0x00FF00);
     }
 }
 
@@ -88,12 +101,12 @@ fn main() {
     println!("==================================================================================================");
     println!("No profiler feature flags were enabled. Since this is an example, this is probably a mistake.");
     println!("Please compile with a feature enabled to run this example.");
-    println!("");
+    println!();
     println!("Example:");
     println!("    cargo run --example simple --features=\"profile-with-tracy\"");
-    println!("");
+    println!();
     println!("Supported feature flags are documented here: https://github.com/aclysma/profiling#feature-flags");
-    println!("");
+    println!();
     println!("Alternatively, try the demo-puffin example:");
     println!("    cd demo-puffin");
     println!("    cargo run --package demo-puffin");
@@ -176,11 +189,40 @@ fn main() {
         burn_time(5);
     }
 
+    // Names can be formatted too. Backends that need static names use the format string as the
+    // name and record the formatted name as data, so this works with every backend.
+    {
+        let worker_index = 3;
+        profiling::scope!(name = "Worker {}", worker_index);
+        burn_time(5);
+    }
+
+    // Scopes can be ended early, or wrap a single expression
+    {
+        let guard = profiling::scope_guard!("Ended early");
+        burn_time(5);
+        drop(guard);
+
+        let total: u128 = profiling::scoped!("Expression scope", { (1..=5).sum() });
+        burn_time(total);
+    }
+
+    // Callback-driven code can begin and end scopes in separate calls
+    {
+        profiling::begin_scope("Manual scope");
+        burn_time(5);
+        profiling::end_scope();
+    }
+
     loop {
         // Generate some profiling info
         profiling::scope!("Main Thread");
         some_function();
+
+        // Work that doesn't run back-to-back can be tracked as discontinuous frames
+        profiling::frame_start!("Other function");
         some_other_function(10);
+        profiling::frame_end!("Other function");
 
         Foo::function1();
         Foo::function2();
@@ -188,6 +230,7 @@ fn main() {
         for i in 0..10 {
             function_scope_function();
             function_scope_function_with_data(i);
+            fields_function(i);
             burn_time(1);
         }
 
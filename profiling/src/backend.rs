@@ -0,0 +1,181 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// A profiler backend that can be selected at runtime with [`set_backend`]. Only backends that
+/// were compiled in with their `profile-with-*` feature will receive anything.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    Puffin,
+    Optick,
+    Superluminal,
+    Tracing,
+    Tracy,
+    /// A [`ProfilerBackend`](crate::ProfilerBackend) registered with `profile-with-custom`
+    Custom,
+    /// The in-process recorder from `profile-with-builtin`, see [`crate::builtin`]
+    Builtin,
+}
+
+impl Backend {
+    /// All backends, whether or not they are compiled in
+    pub const ALL: &'static [Backend] = &[
+        Backend::Puffin,
+        Backend::Optick,
+        Backend::Superluminal,
+        Backend::Tracing,
+        Backend::Tracy,
+        Backend::Custom,
+        Backend::Builtin,
+    ];
+
+    /// The name used by `PROFILING_BACKEND`, i.e. "puffin" or "tracy"
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Puffin => "puffin",
+            Backend::Optick => "optick",
+            Backend::Superluminal => "superluminal",
+            Backend::Tracing => "tracing",
+            Backend::Tracy => "tracy",
+            Backend::Custom => "custom",
+            Backend::Builtin => "builtin",
+        }
+    }
+
+    /// Looks up a backend by the name returned from [`Backend::name`], ignoring ASCII case
+    pub fn from_name(name: &str) -> Option<Backend> {
+        Backend::ALL
+            .iter()
+            .copied()
+            .find(|backend| backend.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns true if the feature flag for this backend is enabled
+    // COMPILED_IN is zero when no backends are enabled
+    #[allow(clippy::bad_bit_mask)]
+    pub fn is_compiled_in(self) -> bool {
+        COMPILED_IN & self.bit() != 0
+    }
+
+    #[inline(always)]
+    const fn bit(self) -> u8 {
+        match self {
+            Backend::Puffin => 1 << 0,
+            Backend::Optick => 1 << 1,
+            Backend::Superluminal => 1 << 2,
+            Backend::Tracing => 1 << 3,
+            Backend::Tracy => 1 << 4,
+            Backend::Custom => 1 << 5,
+            Backend::Builtin => 1 << 6,
+        }
+    }
+}
+
+const fn bit_if(
+    compiled_in: bool,
+    backend: Backend,
+) -> u8 {
+    if compiled_in {
+        backend.bit()
+    } else {
+        0
+    }
+}
+
+const COMPILED_IN: u8 = bit_if(cfg!(feature = "profile-with-puffin"), Backend::Puffin)
+    | bit_if(cfg!(feature = "profile-with-optick"), Backend::Optick)
+    | bit_if(
+        cfg!(feature = "profile-with-superluminal"),
+        Backend::Superluminal,
+    )
+    | bit_if(cfg!(feature = "profile-with-tracing"), Backend::Tracing)
+    | bit_if(cfg!(feature = "profile-with-tracy"), Backend::Tracy)
+    | bit_if(cfg!(feature = "profile-with-custom"), Backend::Custom)
+    | bit_if(cfg!(feature = "profile-with-builtin"), Backend::Builtin);
+
+// Every compiled-in backend is enabled until something else is selected
+static ENABLED: AtomicU8 = AtomicU8::new(COMPILED_IN);
+
+/// Selects the only backend that the macros will send to, or `None` to send to no backend at all.
+/// This only matters when several backends are compiled in, or to turn profiling off at runtime.
+///
+/// ```
+/// profiling::set_backend(profiling::Backend::Puffin);
+/// profiling::set_backend(None);
+/// ```
+pub fn set_backend(backend: impl Into<Option<Backend>>) {
+    let enabled = backend.into().map(|backend| backend.bit()).unwrap_or(0);
+    ENABLED.store(enabled & COMPILED_IN, Ordering::Relaxed);
+}
+
+/// Selects the backend named by the `PROFILING_BACKEND` environment variable (i.e.
+/// `PROFILING_BACKEND=tracy`), or no backend if it is set to "none". Returns false and leaves the
+/// selection unchanged if the variable is not set or does not name a compiled-in backend.
+pub fn set_backend_from_env() -> bool {
+    #[cfg(any(
+        feature = "profile-with-puffin",
+        feature = "profile-with-optick",
+        feature = "profile-with-superluminal",
+        feature = "profile-with-tracing",
+        feature = "profile-with-tracy",
+        feature = "profile-with-custom",
+        feature = "profile-with-builtin",
+        feature = "type-check"
+    ))]
+    {
+        extern crate std;
+
+        if let Ok(name) = std::env::var("PROFILING_BACKEND") {
+            if name.eq_ignore_ascii_case("none") {
+                set_backend(None);
+                return true;
+            }
+
+            if let Some(backend) =
+                Backend::from_name(&name).filter(|backend| backend.is_compiled_in())
+            {
+                set_backend(backend);
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// Returns true if the macros currently send to the given backend
+#[inline(always)]
+pub fn is_backend_enabled(backend: Backend) -> bool {
+    ENABLED.load(Ordering::Relaxed) & backend.bit() != 0
+}
+
+/// Returns true if the macros currently send to any backend
+#[inline(always)]
+pub fn is_any_backend_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    #[test]
+    fn env_backend_must_be_compiled_in() {
+        // Other tests rely on the selection, so this only tries a backend that can't change it
+        let missing = match Backend::ALL
+            .iter()
+            .find(|backend| !backend.is_compiled_in())
+        {
+            Some(backend) => backend,
+            None => return,
+        };
+        let enabled = ENABLED.load(Ordering::Relaxed);
+
+        std::env::set_var("PROFILING_BACKEND", missing.name());
+        assert!(!set_backend_from_env());
+        std::env::remove_var("PROFILING_BACKEND");
+
+        assert_eq!(ENABLED.load(Ordering::Relaxed), enabled);
+    }
+}
@@ -0,0 +1,176 @@
+//! Writes captures in the [callgrind format](https://valgrind.org/docs/manual/cl-format.html),
+//! which can be opened in KCachegrind and QCachegrind to browse the call graph of nested scopes.
+//!
+//! ```
+//! profiling::register_thread!("Main Thread");
+//! {
+//!     profiling::scope!("Update");
+//!     profiling::scope!("Physics");
+//! }
+//!
+//! let mut callgrind = Vec::new();
+//! profiling::builtin::callgrind::write(&profiling::builtin::drain(), &mut callgrind).unwrap();
+//! ```
+
+use super::Capture;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::vec::Vec;
+
+/// Writes each distinct scope name and location as a function with its exclusive time, and each
+/// parent/child pair of scopes as a call with its count and inclusive time, all in nanoseconds.
+/// Threads are merged into a single call graph. Frame ends and scope `data` are not written.
+pub fn write(
+    capture: &Capture,
+    writer: impl Write,
+) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+
+    let mut functions = Vec::new();
+    let mut function_ids = HashMap::new();
+    let mut self_ns = Vec::<u64>::new();
+    // Keyed by caller and callee id, counting calls and their inclusive time
+    let mut calls = BTreeMap::<(usize, usize), (u64, u64)>::new();
+
+    for thread in &capture.threads {
+        let spans = thread.spans();
+        let mut span_functions = Vec::with_capacity(spans.len());
+        for span in &spans {
+            let key = (span.name, span.location.file, span.location.line);
+            let id = *function_ids.entry(key).or_insert_with(|| {
+                functions.push(key);
+                self_ns.push(0);
+                functions.len() - 1
+            });
+            span_functions.push(id);
+
+            let duration_ns = span.end_ns - span.begin_ns;
+            self_ns[id] += duration_ns;
+            if let Some(parent) = span.parent {
+                // Spans are ordered by when they began, so the parent's function is already known
+                let parent_id = span_functions[parent];
+                self_ns[parent_id] = self_ns[parent_id].saturating_sub(duration_ns);
+
+                let call = calls.entry((parent_id, id)).or_default();
+                call.0 += 1;
+                call.1 += duration_ns;
+            }
+        }
+    }
+
+    writeln!(writer, "# callgrind format")?;
+    writeln!(writer, "version: 1")?;
+    writeln!(writer, "creator: profiling")?;
+    writeln!(writer, "positions: line")?;
+    writeln!(writer, "events: ns")?;
+    writeln!(writer, "summary: {}", self_ns.iter().sum::<u64>())?;
+
+    // Names and files are written in full the first time and by id after that
+    let mut file_ids = HashMap::new();
+    let mut named_functions = std::vec![false; functions.len()];
+
+    let mut calls = calls.into_iter().peekable();
+    for (id, &(name, file, line)) in functions.iter().enumerate() {
+        writeln!(writer)?;
+        write_file(&mut writer, "fl", file, &mut file_ids)?;
+        write_function(&mut writer, "fn", id, name, &mut named_functions)?;
+        writeln!(writer, "{} {}", line, self_ns[id])?;
+
+        // The call graph is ordered by caller, which is also the order functions are written in
+        while let Some(((_, callee), (count, inclusive_ns))) =
+            calls.next_if(|((caller, _), _)| *caller == id)
+        {
+            let (callee_name, callee_file, callee_line) = functions[callee];
+            write_file(&mut writer, "cfi", callee_file, &mut file_ids)?;
+            write_function(
+                &mut writer,
+                "cfn",
+                callee,
+                callee_name,
+                &mut named_functions,
+            )?;
+            writeln!(writer, "calls={} {}", count, callee_line)?;
+            // The exact line of the call isn't known, so attribute it to the caller's scope
+            writeln!(writer, "{} {}", line, inclusive_ns)?;
+        }
+    }
+
+    writer.flush()
+}
+
+fn write_file<'a>(
+    writer: &mut impl Write,
+    key: &str,
+    file: &'a str,
+    file_ids: &mut HashMap<&'a str, usize>,
+) -> io::Result<()> {
+    match file_ids.get(file) {
+        Some(id) => writeln!(writer, "{}=({})", key, id),
+        None => {
+            let id = file_ids.len() + 1;
+            file_ids.insert(file, id);
+            writeln!(writer, "{}=({}) {}", key, id, sanitize(file))
+        }
+    }
+}
+
+fn write_function(
+    writer: &mut impl Write,
+    key: &str,
+    id: usize,
+    name: &str,
+    named_functions: &mut [bool],
+) -> io::Result<()> {
+    if named_functions[id] {
+        writeln!(writer, "{}=({})", key, id + 1)
+    } else {
+        named_functions[id] = true;
+        writeln!(writer, "{}=({}) {}", key, id + 1, sanitize(name))
+    }
+}
+
+// Every record is a single line
+fn sanitize(name: &str) -> std::borrow::Cow<'_, str> {
+    if name.contains(['\n', '\r']) {
+        name.replace(['\n', '\r'], " ").into()
+    } else {
+        name.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::test_capture;
+    use std::string::String;
+    use std::vec::Vec;
+
+    #[test]
+    fn functions_and_calls() {
+        let mut output = Vec::new();
+        super::write(&test_capture(), &mut output).unwrap();
+
+        // outer runs for 8µs, 3µs of which are spent in inner. Each function line holds its
+        // exclusive time and each call its inclusive time.
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# callgrind format\n\
+             version: 1\n\
+             creator: profiling\n\
+             positions: line\n\
+             events: ns\n\
+             summary: 8000\n\
+             \n\
+             fl=(1) src/app.rs\n\
+             fn=(1) outer\n\
+             10 5000\n\
+             cfi=(1)\n\
+             cfn=(2) inner\n\
+             calls=1 10\n\
+             10 3000\n\
+             \n\
+             fl=(1)\n\
+             fn=(2)\n\
+             10 3000\n"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Writes captures in the [Chrome Trace Event format](https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU),
+//! which can be opened in chrome://tracing, [Perfetto UI](https://ui.perfetto.dev) and many other
+//! tools.
+//!
+//! ```
+//! profiling::register_thread!("Main Thread");
+//! {
+//!     profiling::scope!("Load", "level1.dat");
+//! }
+//! profiling::finish_frame!();
+//!
+//! let mut json = Vec::new();
+//! profiling::builtin::chrome::write(&profiling::builtin::drain(), &mut json).unwrap();
+//! ```
+
+use super::json;
+use super::Capture;
+use crate::{AsFieldValue, MarkerScope};
+use std::io::{self, Write};
+
+// Everything is recorded in this process
+const PID: u32 = 1;
+
+/// Writes every thread's spans as complete ("X") events, frame ends as global instant events named
+/// "Frame" or after their frame set, discontinuous frames and the lifetimes of tasks as async
+/// ("b"/"e") events, work handed off between threads as flow ("s"/"f") events, markers as
+/// instant events of the same scope, messages as thread instant events, plot values as counter
+/// ("C") events and thread names as thread_name metadata. The `data` of a scope or marker, the
+/// fields of a scope and the color of a scope or message are written to the event's args.
+pub fn write(
+    capture: &Capture,
+    writer: impl Write,
+) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+    let mut separator = "";
+    writer.write_all(b"{\"traceEvents\":[")?;
+
+    for thread in &capture.threads {
+        write!(
+            writer,
+            "{}\n{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{},\"tid\":{},\"args\":{{\"name\":",
+            separator, PID, thread.id
+        )?;
+        json::write_str(&mut writer, &thread.name)?;
+        writer.write_all(b"}}")?;
+        separator = ",";
+
+        for span in thread.spans() {
+            writer.write_all(b",\n{\"name\":")?;
+            json::write_str(&mut writer, span.name)?;
+            writer.write_all(b",\"ph\":\"X\",\"ts\":")?;
+            json::write_us(&mut writer, span.begin_ns)?;
+            writer.write_all(b",\"dur\":")?;
+            json::write_us(&mut writer, span.end_ns - span.begin_ns)?;
+            write!(writer, ",\"pid\":{},\"tid\":{}", PID, thread.id)?;
+            let mut args_separator = ",\"args\":{";
+            if let Some(data) = span.data {
+                writer.write_all(args_separator.as_bytes())?;
+                writer.write_all(b"\"data\":")?;
+                json::write_str(&mut writer, data)?;
+                args_separator = ",";
+            }
+            if let Some(color) = span.color {
+                writer.write_all(args_separator.as_bytes())?;
+                write!(writer, "\"color\":\"#{:06x}\"", color)?;
+                args_separator = ",";
+            }
+            for (key, value) in span.fields {
+                writer.write_all(args_separator.as_bytes())?;
+                json::write_str(&mut writer, key)?;
+                writer.write_all(b":")?;
+                json::write_field_value(&mut writer, value.as_field_value())?;
+                args_separator = ",";
+            }
+            if args_separator == "," {
+                writer.write_all(b"}")?;
+            }
+            writer.write_all(b"}")?;
+        }
+
+        for time_ns in thread.frame_ends_ns() {
+            writer.write_all(b",\n{\"name\":\"Frame\",\"ph\":\"i\",\"s\":\"g\",\"ts\":")?;
+            json::write_us(&mut writer, time_ns)?;
+            write!(writer, ",\"pid\":{},\"tid\":{}}}", PID, thread.id)?;
+        }
+
+        for (time_ns, name) in thread.named_frame_ends_ns() {
+            writer.write_all(b",\n{\"name\":")?;
+            json::write_str(&mut writer, name)?;
+            writer.write_all(b",\"cat\":\"frame\",\"ph\":\"i\",\"s\":\"g\",\"ts\":")?;
+            json::write_us(&mut writer, time_ns)?;
+            write!(writer, ",\"pid\":{},\"tid\":{}}}", PID, thread.id)?;
+        }
+
+        // Async events are paired up by category and id, so each frame set uses its name as the id
+        for frame in thread.discontinuous_frames() {
+            for (phase, time_ns) in [("b", frame.begin_ns), ("e", frame.end_ns)] {
+                writer.write_all(b",\n{\"name\":")?;
+                json::write_str(&mut writer, frame.name)?;
+                writer.write_all(b",\"cat\":\"frame\",\"id2\":{\"local\":")?;
+                json::write_str(&mut writer, frame.name)?;
+                write!(writer, "}},\"ph\":\"{}\",\"ts\":", phase)?;
+                json::write_us(&mut writer, time_ns)?;
+                write!(writer, ",\"pid\":{},\"tid\":{}}}", PID, thread.id)?;
+            }
+        }
+
+        for (time_ns, name, data, scope) in thread.markers() {
+            let scope = match scope {
+                MarkerScope::Thread => "t",
+                MarkerScope::Process => "p",
+                MarkerScope::Global => "g",
+            };
+            writer.write_all(b",\n{\"name\":")?;
+            json::write_str(&mut writer, name)?;
+            write!(writer, ",\"ph\":\"i\",\"s\":\"{}\",\"ts\":", scope)?;
+            json::write_us(&mut writer, time_ns)?;
+            write!(writer, ",\"pid\":{},\"tid\":{}", PID, thread.id)?;
+            if let Some(data) = data {
+                writer.write_all(b",\"args\":{\"data\":")?;
+                json::write_str(&mut writer, data)?;
+                writer.write_all(b"}")?;
+            }
+            writer.write_all(b"}")?;
+        }
+
+        for (time_ns, text, color) in thread.messages() {
+            writer.write_all(b",\n{\"name\":")?;
+            json::write_str(&mut writer, text)?;
+            writer.write_all(b",\"ph\":\"i\",\"s\":\"t\",\"ts\":")?;
+            json::write_us(&mut writer, time_ns)?;
+            write!(writer, ",\"pid\":{},\"tid\":{}", PID, thread.id)?;
+            if let Some(color) = color {
+                write!(writer, ",\"args\":{{\"color\":\"#{:06x}\"}}", color)?;
+            }
+            writer.write_all(b"}")?;
+        }
+
+        // JSON has no representation for NaN or infinity
+        for (time_ns, name, value) in thread.plot_values() {
+            if value.is_finite() {
+                writer.write_all(b",\n{\"name\":")?;
+                json::write_str(&mut writer, name)?;
+                writer.write_all(b",\"ph\":\"C\",\"ts\":")?;
+                json::write_us(&mut writer, time_ns)?;
+                write!(
+                    writer,
+                    ",\"pid\":{},\"tid\":{},\"args\":{{\"value\":{}}}}}",
+                    PID, thread.id, value
+                )?;
+            }
+        }
+    }
+
+    // Tasks can end on a different thread than they began on, so their ids are global
+    for task in capture.tasks() {
+        for (phase, time_ns) in [("b", task.begin_ns), ("e", task.end_ns)] {
+            writer.write_all(separator.as_bytes())?;
+            writer.write_all(b"\n{\"name\":")?;
+            json::write_str(&mut writer, task.name)?;
+            write!(
+                writer,
+                ",\"cat\":\"task\",\"id2\":{{\"global\":\"{}\"}},\"ph\":\"{}\",\"ts\":",
+                task.id, phase
+            )?;
+            json::write_us(&mut writer, time_ns)?;
+            write!(writer, ",\"pid\":{},\"tid\":{}}}", PID, task.thread_id)?;
+            separator = ",";
+        }
+    }
+
+    // Each end of a flow gets its own id, since a flow with several ends would otherwise be drawn
+    // as a chain. The end binds to the slice that encloses it rather than the next one.
+    for (index, flow) in capture.flows().iter().enumerate() {
+        writer.write_all(separator.as_bytes())?;
+        write!(
+            writer,
+            "\n{{\"name\":\"Handoff\",\"cat\":\"flow\",\"id\":{},\"ph\":\"s\",\"ts\":",
+            index + 1
+        )?;
+        json::write_us(&mut writer, flow.from_ns)?;
+        write!(writer, ",\"pid\":{},\"tid\":{}}}", PID, flow.from_thread_id)?;
+        write!(
+            writer,
+            ",\n{{\"name\":\"Handoff\",\"cat\":\"flow\",\"id\":{},\"ph\":\"f\",\"bp\":\"e\",\"ts\":",
+            index + 1
+        )?;
+        json::write_us(&mut writer, flow.to_ns)?;
+        write!(writer, ",\"pid\":{},\"tid\":{}}}", PID, flow.to_thread_id)?;
+        separator = ",";
+    }
+
+    writer.write_all(b"\n],\"displayTimeUnit\":\"ns\"}\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::test_capture;
+    use serde_json::{json, Value};
+    use std::vec::Vec;
+
+    #[test]
+    fn nested_scopes_and_message() {
+        let mut output = Vec::new();
+        super::write(&test_capture(), &mut output).unwrap();
+        let trace: Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(trace["displayTimeUnit"], "ns");
+        assert_eq!(
+            trace["traceEvents"],
+            json!([
+                {
+                    "name": "thread_name",
+                    "ph": "M",
+                    "pid": 1,
+                    "tid": 1,
+                    "args": { "name": "Main Thread" }
+                },
+                {
+                    "name": "outer",
+                    "ph": "X",
+                    "ts": 1.0,
+                    "dur": 8.0,
+                    "pid": 1,
+                    "tid": 1,
+                    "args": { "data": "level1.dat" }
+                },
+                {
+                    "name": "inner",
+                    "ph": "X",
+                    "ts": 2.0,
+                    "dur": 3.0,
+                    "pid": 1,
+                    "tid": 1,
+                    "args": { "color": "#c62828", "bytes": 4096 }
+                },
+                {
+                    "name": "hello",
+                    "ph": "i",
+                    "s": "t",
+                    "ts": 3.0,
+                    "pid": 1,
+                    "tid": 1
+                }
+            ])
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Writes captures in the processed profile format of the
+//! [Firefox Profiler](https://profiler.firefox.com), which can load the file with "Load a profile
+//! from file" or from a URL. Scopes become interval markers in each thread's marker chart.
+//!
+//! ```
+//! profiling::register_thread!("Main Thread");
+//! {
+//!     profiling::scope!("Load", "level1.dat");
+//! }
+//! profiling::finish_frame!();
+//!
+//! let mut json = Vec::new();
+//! profiling::builtin::firefox::write(&profiling::builtin::drain(), &mut json).unwrap();
+//! ```
+
+use super::json;
+use super::{Capture, ThreadCapture};
+use crate::MarkerScope;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::vec::Vec;
+
+// The profiler upgrades older processed profiles on load, so this doesn't need to track the latest
+// version
+const PREPROCESSED_PROFILE_VERSION: u32 = 46;
+const GECKO_PROFILE_VERSION: u32 = 27;
+
+// Marker phases
+const INSTANT: u32 = 0;
+const INTERVAL: u32 = 1;
+
+// Everything is recorded in this process
+const PID: &str = "1";
+
+const META: &str = r#""meta":{"interval":1,"startTime":0,"processType":0,"product":"profiling","stackwalk":0,"symbolicated":true,
+"categories":[{"name":"Other","color":"grey","subcategories":["Other"]}],
+"markerSchema":[{"name":"Scope","display":["marker-chart","marker-table","timeline-overview"],"tableLabel":"{marker.name} {marker.data.data}","chartLabel":"{marker.data.data}","data":[{"key":"data","label":"Data","format":"string","searchable":true}]},
+{"name":"Marker","display":["marker-chart","marker-table","timeline-overview"],"tableLabel":"{marker.name} {marker.data.data}","chartLabel":"{marker.data.data}","data":[{"key":"data","label":"Data","format":"string","searchable":true},{"key":"scope","label":"Scope","format":"string"}]}],"#;
+
+// Sampling is not supported, so every thread has the same empty tables
+const EMPTY_TABLES: &str = r#""samples":{"weightType":"samples","weight":null,"stack":[],"time":[],"length":0},
+"stackTable":{"frame":[],"prefix":[],"category":[],"subcategory":[],"length":0},
+"frameTable":{"address":[],"inlineDepth":[],"category":[],"subcategory":[],"func":[],"nativeSymbol":[],"innerWindowID":[],"implementation":[],"line":[],"column":[],"length":0},
+"funcTable":{"isJS":[],"relevantForJS":[],"name":[],"resource":[],"fileName":[],"lineNumber":[],"columnNumber":[],"length":0},
+"resourceTable":{"lib":[],"name":[],"host":[],"type":[],"length":0},
+"nativeSymbols":{"libIndex":[],"address":[],"name":[],"functionSize":[],"length":0},"#;
+
+/// Writes every thread's spans and discontinuous frames as interval markers, `marker!` and messages
+/// as instant markers and frame ends as instant markers named "Frame" or after their frame set. The
+/// `data` of a scope or marker is written as the marker's payload, which shows up in its label and
+/// tooltip and can be searched.
+pub fn write(
+    capture: &Capture,
+    writer: impl Write,
+) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+    writer.write_all(b"{")?;
+    writer.write_all(META.as_bytes())?;
+    write!(
+        writer,
+        "\"version\":{},\"preprocessedProfileVersion\":{}}},\n\"libs\":[],\"pages\":[],\"counters\":[],\"threads\":[",
+        GECKO_PROFILE_VERSION, PREPROCESSED_PROFILE_VERSION
+    )?;
+
+    let mut separator = "";
+    for (index, thread) in capture.threads.iter().enumerate() {
+        writer.write_all(separator.as_bytes())?;
+        write_thread(&mut writer, thread, index == 0)?;
+        separator = ",";
+    }
+
+    writer.write_all(b"]}\n")?;
+    writer.flush()
+}
+
+struct Marker<'a> {
+    name: usize,
+    payload: Payload<'a>,
+    start_ns: u64,
+    end_ns: Option<u64>,
+}
+
+enum Payload<'a> {
+    None,
+    Scope {
+        data: Option<&'a str>,
+    },
+    Marker {
+        data: Option<&'a str>,
+        scope: MarkerScope,
+    },
+}
+
+fn write_thread(
+    writer: &mut impl Write,
+    thread: &ThreadCapture,
+    is_main_thread: bool,
+) -> io::Result<()> {
+    // Marker names are indices into the thread's string array
+    let mut strings = Vec::new();
+    let mut string_indices = HashMap::new();
+    let mut intern = |string| {
+        *string_indices.entry(string).or_insert_with(|| {
+            strings.push(string);
+            strings.len() - 1
+        })
+    };
+
+    let mut markers = Vec::new();
+    for span in thread.spans() {
+        markers.push(Marker {
+            name: intern(span.name),
+            payload: Payload::Scope { data: span.data },
+            start_ns: span.begin_ns,
+            end_ns: Some(span.end_ns),
+        });
+    }
+    for (time_ns, name, data, scope) in thread.markers() {
+        markers.push(Marker {
+            name: intern(name),
+            payload: Payload::Marker { data, scope },
+            start_ns: time_ns,
+            end_ns: None,
+        });
+    }
+    for (time_ns, text, _) in thread.messages() {
+        markers.push(Marker {
+            name: intern(text),
+            payload: Payload::None,
+            start_ns: time_ns,
+            end_ns: None,
+        });
+    }
+    for time_ns in thread.frame_ends_ns() {
+        markers.push(Marker {
+            name: intern("Frame"),
+            payload: Payload::None,
+            start_ns: time_ns,
+            end_ns: None,
+        });
+    }
+    for (time_ns, name) in thread.named_frame_ends_ns() {
+        markers.push(Marker {
+            name: intern(name),
+            payload: Payload::None,
+            start_ns: time_ns,
+            end_ns: None,
+        });
+    }
+    for frame in thread.discontinuous_frames() {
+        markers.push(Marker {
+            name: intern(frame.name),
+            payload: Payload::None,
+            start_ns: frame.begin_ns,
+            end_ns: Some(frame.end_ns),
+        });
+    }
+
+    write!(
+        writer,
+        "\n{{\"processType\":\"default\",\"processStartupTime\":0,\"processShutdownTime\":null,\"registerTime\":0,\"unregisterTime\":null,\"pausedRanges\":[],\"isMainThread\":{},\"pid\":\"{}\",\"tid\":{},\"name\":",
+        is_main_thread, PID, thread.id
+    )?;
+    json::write_str(writer, &thread.name)?;
+    writer.write_all(b",\n")?;
+    writer.write_all(EMPTY_TABLES.as_bytes())?;
+
+    writer.write_all(b"\n\"markers\":{\"data\":[")?;
+    write_column(writer, &markers, |writer, marker| {
+        let data = match marker.payload {
+            Payload::None => return writer.write_all(b"null"),
+            Payload::Scope { data } => {
+                writer.write_all(b"{\"type\":\"Scope\"")?;
+                data
+            }
+            Payload::Marker { data, scope } => {
+                write!(
+                    writer,
+                    "{{\"type\":\"Marker\",\"scope\":\"{}\"",
+                    scope.name()
+                )?;
+                data
+            }
+        };
+        if let Some(data) = data {
+            writer.write_all(b",\"data\":")?;
+            json::write_str(writer, data)?;
+        }
+        writer.write_all(b"}")
+    })?;
+    writer.write_all(b"],\"name\":[")?;
+    write_column(writer, &markers, |writer, marker| {
+        write!(writer, "{}", marker.name)
+    })?;
+    writer.write_all(b"],\"startTime\":[")?;
+    write_column(writer, &markers, |writer, marker| {
+        json::write_ms(writer, marker.start_ns)
+    })?;
+    writer.write_all(b"],\"endTime\":[")?;
+    write_column(writer, &markers, |writer, marker| match marker.end_ns {
+        Some(end_ns) => json::write_ms(writer, end_ns),
+        None => writer.write_all(b"null"),
+    })?;
+    writer.write_all(b"],\"phase\":[")?;
+    write_column(writer, &markers, |writer, marker| {
+        let phase = if marker.end_ns.is_some() {
+            INTERVAL
+        } else {
+            INSTANT
+        };
+        write!(writer, "{}", phase)
+    })?;
+    writer.write_all(b"],\"category\":[")?;
+    write_column(writer, &markers, |writer, _| writer.write_all(b"0"))?;
+    write!(
+        writer,
+        "],\"length\":{}}},\n\"stringArray\":[",
+        markers.len()
+    )?;
+    write_column(writer, &strings, |writer, string| {
+        json::write_str(writer, string)
+    })?;
+    writer.write_all(b"]}")
+}
+
+// The profiler stores tables as one array per column
+fn write_column<W: Write, T>(
+    writer: &mut W,
+    rows: &[T],
+    mut write_value: impl FnMut(&mut W, &T) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut separator = "";
+    for row in rows {
+        writer.write_all(separator.as_bytes())?;
+        write_value(writer, row)?;
+        separator = ",";
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::test_capture;
+    use serde_json::{json, Value};
+    use std::vec::Vec;
+
+    #[test]
+    fn markers_and_string_table() {
+        let mut output = Vec::new();
+        super::write(&test_capture(), &mut output).unwrap();
+        let profile: Value = serde_json::from_slice(&output).unwrap();
+
+        let meta = &profile["meta"];
+        assert_eq!(meta["version"], 27);
+        assert_eq!(meta["preprocessedProfileVersion"], 46);
+        let schema_names: Vec<_> = meta["markerSchema"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|schema| &schema["name"])
+            .collect();
+        assert_eq!(schema_names, ["Scope", "Marker"]);
+
+        let threads = profile["threads"].as_array().unwrap();
+        assert_eq!(threads.len(), 1);
+        let thread = &threads[0];
+        assert_eq!(thread["name"], "Main Thread");
+        assert_eq!(thread["tid"], 1);
+        assert_eq!(thread["isMainThread"], true);
+
+        // Spans come first in the order they began, then messages. Times are in milliseconds.
+        assert_eq!(thread["stringArray"], json!(["outer", "inner", "hello"]));
+        assert_eq!(
+            thread["markers"],
+            json!({
+                "data": [
+                    { "type": "Scope", "data": "level1.dat" },
+                    { "type": "Scope" },
+                    null
+                ],
+                "name": [0, 1, 2],
+                "startTime": [0.001, 0.002, 0.003],
+                "endTime": [0.009, 0.005, null],
+                "phase": [1, 1, 0],
+                "category": [0, 0, 0],
+                "length": 3
+            })
+        );
+    }
+}
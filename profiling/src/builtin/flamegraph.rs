@@ -0,0 +1,313 @@
+//! Renders folded stacks, such as those written by [`folded`](super::folded), as a standalone
+//! interactive SVG flamegraph. Hover over a frame to see its time, click it to zoom in and click
+//! "Reset Zoom" to zoom back out. Open the file in a web browser for the interactive parts to work.
+//!
+//! ```
+//! use profiling::builtin::folded::{self, Weight};
+//!
+//! profiling::register_thread!("Main Thread");
+//! {
+//!     profiling::scope!("Update");
+//!     profiling::scope!("Physics");
+//! }
+//!
+//! let mut stacks = Vec::new();
+//! folded::write(&profiling::builtin::drain(), Weight::SelfTime, &mut stacks).unwrap();
+//!
+//! let mut svg = Vec::new();
+//! profiling::builtin::flamegraph::write(&String::from_utf8(stacks).unwrap(), &mut svg).unwrap();
+//! ```
+
+use std::io::{self, Write};
+use std::string::String;
+use std::vec::Vec;
+
+const IMAGE_WIDTH: f64 = 1200.0;
+const PADDING: f64 = 10.0;
+const TITLE_HEIGHT: f64 = 40.0;
+const FRAME_HEIGHT: f64 = 16.0;
+const FONT_SIZE: f64 = 12.0;
+// Approximate width of a character in the monospace font, relative to the font size
+const FONT_WIDTH: f64 = 0.59;
+
+const SCRIPT: &str = r#"
+var frames = document.querySelectorAll(".frame");
+function label(g, width) {
+    var text = g.querySelector("text");
+    var name = g.getAttribute("data-name");
+    var chars = Math.floor((width - 6) / (FONT_SIZE * FONT_WIDTH));
+    text.textContent = chars < 3 ? "" : name.length <= chars ? name : name.substring(0, chars - 2) + "..";
+}
+function zoom(target) {
+    var x0 = +target.getAttribute("data-x"), w0 = +target.getAttribute("data-w");
+    for (var i = 0; i < frames.length; i++) {
+        var g = frames[i], rect = g.querySelector("rect");
+        var x = +g.getAttribute("data-x"), w = +g.getAttribute("data-w");
+        var left = Math.max(x, x0), right = Math.min(x + w, x0 + w0);
+        if (right <= left) {
+            g.style.display = "none";
+            continue;
+        }
+        var width = (right - left) / w0 * IMAGE_WIDTH;
+        g.style.display = "";
+        rect.setAttribute("x", PADDING + (left - x0) / w0 * IMAGE_WIDTH);
+        rect.setAttribute("width", width);
+        g.querySelector("text").setAttribute("x", PADDING + (left - x0) / w0 * IMAGE_WIDTH + 3);
+        label(g, width);
+    }
+}
+for (var i = 0; i < frames.length; i++) {
+    frames[i].addEventListener("click", function(e) { zoom(e.currentTarget); });
+}
+document.getElementById("reset").addEventListener("click", function() { zoom(frames[0]); });
+"#;
+
+struct Node {
+    name: String,
+    value: u64,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn child(
+        &mut self,
+        name: &str,
+    ) -> &mut Node {
+        // Children are kept sorted by name, like other flamegraph tools
+        let index = match self
+            .children
+            .binary_search_by(|child| child.name.as_str().cmp(name))
+        {
+            Ok(index) => index,
+            Err(index) => {
+                self.children.insert(
+                    index,
+                    Node {
+                        name: name.into(),
+                        value: 0,
+                        children: Vec::new(),
+                    },
+                );
+                index
+            }
+        };
+        &mut self.children[index]
+    }
+
+    fn depth(&self) -> usize {
+        1 + self.children.iter().map(Node::depth).max().unwrap_or(0)
+    }
+}
+
+/// Renders folded stacks as an SVG. Each line of `folded` is a stack of frames separated by `;`
+/// followed by a space and the stack's self time in nanoseconds. Lines that can't be parsed are
+/// skipped.
+pub fn write(
+    folded: &str,
+    writer: impl Write,
+) -> io::Result<()> {
+    let mut root = Node {
+        name: "all".into(),
+        value: 0,
+        children: Vec::new(),
+    };
+    for line in folded.lines() {
+        let (stack, value) = match line.trim_end().rsplit_once(' ') {
+            Some((stack, value)) => match value.parse::<u64>() {
+                Ok(value) => (stack, value),
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+
+        root.value += value;
+        let mut node = &mut root;
+        for frame in stack.split(';') {
+            node = node.child(frame);
+            node.value += value;
+        }
+    }
+
+    let mut writer = io::BufWriter::new(writer);
+    let depth = root.depth();
+    let image_height = TITLE_HEIGHT + depth as f64 * FRAME_HEIGHT + PADDING;
+
+    write!(
+        writer,
+        r##"<?xml version="1.0" standalone="no"?>
+<svg version="1.1" width="{width}" height="{height}" viewBox="0 0 {width} {height}" xmlns="http://www.w3.org/2000/svg">
+<style>text {{ font-family: monospace; font-size: {font_size}px; fill: black; }} .frame {{ cursor: pointer; }} .frame:hover rect {{ stroke: black; stroke-width: 0.5; }} #reset {{ cursor: pointer; }}</style>
+<rect x="0" y="0" width="100%" height="100%" fill="#eeeeee"/>
+<text x="{center}" y="24" text-anchor="middle" style="font-size: 17px">Flame Graph</text>
+<text id="reset" x="{padding}" y="24">Reset Zoom</text>
+"##,
+        width = IMAGE_WIDTH + 2.0 * PADDING,
+        height = image_height,
+        font_size = FONT_SIZE,
+        center = (IMAGE_WIDTH + 2.0 * PADDING) / 2.0,
+        padding = PADDING,
+    )?;
+
+    if root.value > 0 {
+        write_node(&mut writer, &root, root.value, 0.0, 0, image_height)?;
+    }
+
+    write!(
+        writer,
+        "<script><![CDATA[\nvar IMAGE_WIDTH = {}, PADDING = {}, FONT_SIZE = {}, FONT_WIDTH = {};{}]]></script>\n</svg>\n",
+        IMAGE_WIDTH, PADDING, FONT_SIZE, FONT_WIDTH, SCRIPT
+    )?;
+    writer.flush()
+}
+
+// Frames are positioned in units of nanoseconds (x) so the script can rescale them when zooming
+fn write_node(
+    writer: &mut impl Write,
+    node: &Node,
+    total: u64,
+    x: f64,
+    depth: usize,
+    image_height: f64,
+) -> io::Result<()> {
+    let width = node.value as f64 / total as f64 * IMAGE_WIDTH;
+    let y = image_height - PADDING - (depth + 1) as f64 * FRAME_HEIGHT;
+    let (r, g, b) = color(&node.name);
+
+    let mut name = String::new();
+    escape(&mut name, &node.name);
+    // Matches the truncation done by the script when zooming
+    let chars = ((width - 6.0) / (FONT_SIZE * FONT_WIDTH)).max(0.0) as usize;
+    let mut label = String::new();
+    if chars >= 3 {
+        if node.name.chars().count() <= chars {
+            label.push_str(&name);
+        } else {
+            let truncated: String = node.name.chars().take(chars - 2).collect();
+            escape(&mut label, &truncated);
+            label.push_str("..");
+        }
+    }
+
+    writeln!(
+        writer,
+        r#"<g class="frame" data-name="{name}" data-x="{data_x}" data-w="{data_w}"><title>{name} ({time}, {percent:.2}%)</title><rect x="{x:.2}" y="{y}" width="{width:.2}" height="{height}" fill="rgb({r},{g},{b})" rx="2"/><text x="{text_x:.2}" y="{text_y}">{label}</text></g>"#,
+        name = name,
+        data_x = x,
+        data_w = node.value,
+        time = format_ns(node.value),
+        percent = node.value as f64 * 100.0 / total as f64,
+        x = PADDING + x / total as f64 * IMAGE_WIDTH,
+        y = y,
+        width = width,
+        height = FRAME_HEIGHT - 1.0,
+        r = r,
+        g = g,
+        b = b,
+        text_x = PADDING + x / total as f64 * IMAGE_WIDTH + 3.0,
+        text_y = y + FRAME_HEIGHT - 4.0,
+        label = label,
+    )?;
+
+    let mut child_x = x;
+    for child in &node.children {
+        write_node(writer, child, total, child_x, depth + 1, image_height)?;
+        child_x += child.value as f64;
+    }
+    Ok(())
+}
+
+fn format_ns(ns: u64) -> String {
+    if ns >= 1_000_000_000 {
+        std::format!("{:.3} s", ns as f64 / 1e9)
+    } else if ns >= 1_000_000 {
+        std::format!("{:.3} ms", ns as f64 / 1e6)
+    } else if ns >= 1_000 {
+        std::format!("{:.3} µs", ns as f64 / 1e3)
+    } else {
+        std::format!("{} ns", ns)
+    }
+}
+
+// The classic warm flamegraph palette, picked from the name so a frame keeps its color across
+// renders
+fn color(name: &str) -> (u8, u8, u8) {
+    let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    });
+    let v1 = (hash & 0xFF) as f64 / 255.0;
+    let v2 = ((hash >> 8) & 0xFF) as f64 / 255.0;
+    (
+        (205.0 + 50.0 * v1) as u8,
+        (230.0 * v2 * 0.9) as u8,
+        (55.0 * v1) as u8,
+    )
+}
+
+fn escape(
+    out: &mut String,
+    value: &str,
+) {
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::folded::{self, Weight};
+    use crate::builtin::test_capture;
+    use std::string::String;
+    use std::vec::Vec;
+
+    #[test]
+    fn one_rect_per_frame() {
+        let mut stacks = Vec::new();
+        folded::write(&test_capture(), Weight::SelfTime, &mut stacks).unwrap();
+        let mut svg = Vec::new();
+        super::write(&String::from_utf8(stacks).unwrap(), &mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+
+        // Each frame is a group on a line of its own, holding its rect, title and label
+        let frames: Vec<&str> = svg
+            .lines()
+            .filter(|line| line.starts_with("<g class=\"frame\""))
+            .collect();
+        let names_and_values: Vec<(&str, &str)> = frames
+            .iter()
+            .map(|frame| (attribute(frame, "data-name"), attribute(frame, "data-w")))
+            .collect();
+        assert_eq!(
+            names_and_values,
+            [
+                ("all", "8000"),
+                ("Main Thread", "8000"),
+                ("outer", "8000"),
+                ("inner", "3000")
+            ]
+        );
+        for frame in &frames {
+            assert_eq!(frame.matches("<rect ").count(), 1);
+        }
+        // Plus the background
+        assert_eq!(svg.matches("<rect ").count(), frames.len() + 1);
+
+        // inner takes up 3/8 of the 1200 pixel wide image
+        assert!(frames[3].contains("width=\"450.00\""));
+    }
+
+    fn attribute<'a>(
+        element: &'a str,
+        name: &str,
+    ) -> &'a str {
+        let start = element.find(&std::format!(" {}=\"", name)).unwrap() + name.len() + 3;
+        let len = element[start..].find('"').unwrap();
+        &element[start..start + len]
+    }
+}
@@ -0,0 +1,117 @@
+//! Writes captures as collapsed "folded" stacks, one line per unique stack of nested scopes
+//! followed by its weight in nanoseconds, i.e. `Main Thread;Update;Physics 1234`. Most flamegraph
+//! tools accept this, including [`flamegraph`](super::flamegraph).
+//!
+//! ```
+//! use profiling::builtin::folded::{self, Weight};
+//!
+//! profiling::register_thread!("Main Thread");
+//! {
+//!     profiling::scope!("Update");
+//!     profiling::scope!("Physics");
+//! }
+//!
+//! let mut stacks = Vec::new();
+//! folded::write(&profiling::builtin::drain(), Weight::SelfTime, &mut stacks).unwrap();
+//! ```
+
+use super::Capture;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::string::String;
+use std::vec::Vec;
+
+/// How much each stack counts for
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Weight {
+    /// Time spent in the innermost scope of the stack, excluding nested scopes. This is what
+    /// flamegraphs expect.
+    SelfTime,
+    /// Time spent in the innermost scope of the stack, including nested scopes
+    Inclusive,
+}
+
+/// Writes the stacks of every thread, each starting with the thread's name. Identical stacks are
+/// merged and lines are sorted, so the output is stable across captures of the same workload.
+/// Stacks with a weight of zero are left out.
+pub fn write(
+    capture: &Capture,
+    weight: Weight,
+    writer: impl Write,
+) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+    let mut stacks = BTreeMap::<String, u64>::new();
+
+    for thread in &capture.threads {
+        let spans = thread.spans();
+
+        let mut child_ns = std::vec![0; spans.len()];
+        for span in &spans {
+            if let Some(parent) = span.parent {
+                child_ns[parent] += span.end_ns - span.begin_ns;
+            }
+        }
+
+        // Spans are ordered by when they began, so a parent's stack is built before its children's
+        let mut paths: Vec<String> = Vec::with_capacity(spans.len());
+        for (index, span) in spans.iter().enumerate() {
+            let mut path = match span.parent {
+                Some(parent) => paths[parent].clone(),
+                None => sanitize(&thread.name),
+            };
+            path.push(';');
+            path.push_str(&sanitize(span.name));
+
+            let duration_ns = span.end_ns - span.begin_ns;
+            let value = match weight {
+                Weight::SelfTime => duration_ns.saturating_sub(child_ns[index]),
+                Weight::Inclusive => duration_ns,
+            };
+            if value > 0 {
+                *stacks.entry(path.clone()).or_default() += value;
+            }
+            paths.push(path);
+        }
+    }
+
+    for (stack, value) in stacks {
+        writeln!(writer, "{} {}", stack, value)?;
+    }
+    writer.flush()
+}
+
+// Semicolons separate frames and a newline would end the line early
+fn sanitize(name: &str) -> String {
+    name.replace([';', '\n', '\r'], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Weight;
+    use crate::builtin::test_capture;
+    use std::string::String;
+    use std::vec::Vec;
+
+    fn folded(weight: Weight) -> String {
+        let mut output = Vec::new();
+        super::write(&test_capture(), weight, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn self_time_excludes_nested_scopes() {
+        // outer runs for 8µs, 3µs of which are spent in inner
+        assert_eq!(
+            folded(Weight::SelfTime),
+            "Main Thread;outer 5000\nMain Thread;outer;inner 3000\n"
+        );
+    }
+
+    #[test]
+    fn inclusive_time_includes_nested_scopes() {
+        assert_eq!(
+            folded(Weight::Inclusive),
+            "Main Thread;outer 8000\nMain Thread;outer;inner 3000\n"
+        );
+    }
+}
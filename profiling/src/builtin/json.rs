@@ -0,0 +1,66 @@
+// Just enough JSON to write the text-based trace formats without pulling in serde
+
+use crate::FieldValue;
+use std::io::{self, Write};
+
+/// Writes `value` as a quoted, escaped JSON string
+pub(crate) fn write_str(
+    writer: &mut impl Write,
+    value: &str,
+) -> io::Result<()> {
+    writer.write_all(b"\"")?;
+    let mut start = 0;
+    for (index, c) in value.char_indices() {
+        let escaped = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            c if (c as u32) < 0x20 => "",
+            _ => continue,
+        };
+
+        writer.write_all(&value.as_bytes()[start..index])?;
+        if escaped.is_empty() {
+            write!(writer, "\\u{:04x}", c as u32)?;
+        } else {
+            writer.write_all(escaped.as_bytes())?;
+        }
+        start = index + c.len_utf8();
+    }
+    writer.write_all(&value.as_bytes()[start..])?;
+    writer.write_all(b"\"")
+}
+
+/// Formats nanoseconds as the fractional microseconds used by several trace formats
+pub(crate) fn write_us(
+    writer: &mut impl Write,
+    ns: u64,
+) -> io::Result<()> {
+    write!(writer, "{}.{:03}", ns / 1000, ns % 1000)
+}
+
+/// Formats nanoseconds as the fractional milliseconds used by several trace formats
+pub(crate) fn write_ms(
+    writer: &mut impl Write,
+    ns: u64,
+) -> io::Result<()> {
+    write!(writer, "{}.{:06}", ns / 1_000_000, ns % 1_000_000)
+}
+
+/// Writes a scope field as the matching JSON type. JSON has no representation for NaN or infinity,
+/// so those are written as null.
+pub(crate) fn write_field_value(
+    writer: &mut impl Write,
+    value: FieldValue<'_>,
+) -> io::Result<()> {
+    match value {
+        FieldValue::I64(value) => write!(writer, "{}", value),
+        FieldValue::U64(value) => write!(writer, "{}", value),
+        FieldValue::F64(value) if value.is_finite() => write!(writer, "{}", value),
+        FieldValue::F64(_) => writer.write_all(b"null"),
+        FieldValue::Bool(value) => write!(writer, "{}", value),
+        FieldValue::Str(value) => write_str(writer, value),
+    }
+}
@@ -0,0 +1,934 @@
+//! In-process recorder used by the `profile-with-builtin` backend.
+//!
+//! Scopes, thread names and frame boundaries are recorded into per-thread buffers in memory, so
+//! the macros produce usable data without any external profiler. Use [`drain`] or [`snapshot`] to
+//! get at the recorded events.
+//!
+//! ```
+//! profiling::register_thread!("Main Thread");
+//! for _ in 0..3 {
+//!     profiling::scope!("Frame");
+//!     profiling::finish_frame!();
+//! }
+//!
+//! let capture = profiling::builtin::drain();
+//! for thread in &capture.threads {
+//!     println!("{} recorded {} events", thread.name, thread.events.len());
+//! }
+//! ```
+
+pub mod callgrind;
+pub mod chrome;
+pub mod firefox;
+pub mod flamegraph;
+pub mod folded;
+mod json;
+pub mod perfetto;
+pub mod pprof;
+mod protobuf;
+pub mod speedscope;
+
+use crate::lazy::Lazy;
+use crate::{AsFieldValue, FieldValue, MarkerScope, ScopeLocation};
+use core::fmt;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::string::{String, ToString};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+use std::vec::Vec;
+
+/// Everything recorded by the builtin backend, grouped by thread
+#[derive(Clone, Debug, Default)]
+pub struct Capture {
+    pub threads: Vec<ThreadCapture>,
+}
+
+/// Events recorded on a single thread, in the order they happened
+#[derive(Clone, Debug)]
+pub struct ThreadCapture {
+    /// Unique for the lifetime of the process. Assigned in the order threads first record something.
+    pub id: u64,
+    /// The name passed to `register_thread!`, otherwise the name of the thread when it first
+    /// recorded something
+    pub name: String,
+    pub events: Vec<Event>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Event {
+    /// Nanoseconds since the builtin backend recorded its first event
+    pub time_ns: u64,
+    pub kind: EventKind,
+}
+
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum EventKind {
+    /// A scope opened. Scopes are strictly nested per thread and each one is closed by a later
+    /// `ScopeEnd`, though that may not have happened yet when the events are drained.
+    ScopeBegin {
+        name: Cow<'static, str>,
+        data: Option<String>,
+        /// In the form 0xRRGGBB
+        color: Option<u32>,
+        /// Typed fields, i.e. `scope!("load", bytes = n)`. Empty for most scopes.
+        fields: Vec<(&'static str, OwnedFieldValue)>,
+        location: ScopeLocation,
+    },
+    ScopeEnd,
+    /// `finish_frame!` was called on this thread
+    FrameEnd,
+    /// `finish_frame!(name)` was called on this thread
+    NamedFrameEnd {
+        name: &'static str,
+    },
+    /// `frame_start!(name)` was called on this thread
+    DiscontinuousFrameBegin {
+        name: &'static str,
+    },
+    /// `frame_end!(name)` was called on this thread
+    DiscontinuousFrameEnd {
+        name: &'static str,
+    },
+    /// `plot!` recorded a value
+    Plot {
+        name: &'static str,
+        value: f64,
+    },
+    /// `message!` was called on this thread, with the color in the form 0xRRGGBB if one was given
+    Message {
+        text: String,
+        color: Option<u32>,
+    },
+    /// `marker!` recorded a point in time
+    Marker {
+        name: Cow<'static, str>,
+        data: Option<String>,
+        scope: MarkerScope,
+        location: ScopeLocation,
+    },
+    /// A task was created on this thread, i.e. by `profiling::tokio::spawn`. Its polls are
+    /// recorded as scopes on whichever threads run it.
+    TaskBegin {
+        /// Unique for the lifetime of the process
+        id: u64,
+        name: &'static str,
+    },
+    /// The task with this id completed or was dropped on this thread
+    TaskEnd {
+        id: u64,
+    },
+    /// Work was handed off from the current scope on this thread, i.e. to the jobs of a rayon
+    /// iterator or a thread spawned with `profiling::thread`. Each `FlowEnd` with the same id links
+    /// back to this point.
+    FlowBegin {
+        /// Unique for the lifetime of the process
+        id: u64,
+    },
+    /// Work handed off by the `FlowBegin` with this id started running on this thread. Outside of
+    /// any scope, this links to the next scope that begins on the thread.
+    FlowEnd {
+        id: u64,
+    },
+}
+
+/// A discontinuous frame reconstructed from its `DiscontinuousFrameBegin` and
+/// `DiscontinuousFrameEnd` events
+#[derive(Clone, Debug)]
+pub struct DiscontinuousFrame {
+    pub name: &'static str,
+    pub begin_ns: u64,
+    pub end_ns: u64,
+}
+
+/// A task reconstructed from its `TaskBegin` and `TaskEnd` events, which may have been recorded on
+/// different threads
+#[derive(Clone, Debug)]
+pub struct Task {
+    pub id: u64,
+    pub name: &'static str,
+    /// The [`ThreadCapture::id`] of the thread that created the task
+    pub thread_id: u64,
+    pub begin_ns: u64,
+    pub end_ns: u64,
+}
+
+/// A link from the point where work was handed off to where it started running, reconstructed
+/// from a `FlowEnd` and the `FlowBegin` with the same id
+#[derive(Clone, Debug)]
+pub struct Flow {
+    pub id: u64,
+    /// The [`ThreadCapture::id`] of the thread that handed off the work
+    pub from_thread_id: u64,
+    pub from_ns: u64,
+    /// The [`ThreadCapture::id`] of the thread that ran the work
+    pub to_thread_id: u64,
+    pub to_ns: u64,
+}
+
+/// A [`FieldValue`] that owns its string so it can outlive the scope that recorded it
+#[derive(Clone, Debug, PartialEq)]
+pub enum OwnedFieldValue {
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl From<FieldValue<'_>> for OwnedFieldValue {
+    fn from(value: FieldValue<'_>) -> Self {
+        match value {
+            FieldValue::I64(value) => OwnedFieldValue::I64(value),
+            FieldValue::U64(value) => OwnedFieldValue::U64(value),
+            FieldValue::F64(value) => OwnedFieldValue::F64(value),
+            FieldValue::Bool(value) => OwnedFieldValue::Bool(value),
+            FieldValue::Str(value) => OwnedFieldValue::Str(value.to_string()),
+        }
+    }
+}
+
+impl AsFieldValue for OwnedFieldValue {
+    fn as_field_value(&self) -> FieldValue<'_> {
+        match self {
+            OwnedFieldValue::I64(value) => FieldValue::I64(*value),
+            OwnedFieldValue::U64(value) => FieldValue::U64(*value),
+            OwnedFieldValue::F64(value) => FieldValue::F64(*value),
+            OwnedFieldValue::Bool(value) => FieldValue::Bool(*value),
+            OwnedFieldValue::Str(value) => FieldValue::Str(value),
+        }
+    }
+}
+
+/// A scope reconstructed from its `ScopeBegin` and `ScopeEnd` events
+#[derive(Clone, Debug)]
+pub struct Span<'a> {
+    pub name: &'a str,
+    pub data: Option<&'a str>,
+    /// In the form 0xRRGGBB
+    pub color: Option<u32>,
+    pub fields: &'a [(&'static str, OwnedFieldValue)],
+    pub location: &'a ScopeLocation,
+    pub begin_ns: u64,
+    pub end_ns: u64,
+    /// Number of enclosing spans
+    pub depth: usize,
+    /// Index of the enclosing span in the list returned by [`ThreadCapture::spans`]
+    pub parent: Option<usize>,
+}
+
+impl ThreadCapture {
+    /// Pairs up scope events into spans, ordered by when they began. Since a capture may have been
+    /// drained while scopes were open, ends without a begin are ignored and scopes that never
+    /// ended are closed at the time of the thread's last event.
+    pub fn spans(&self) -> Vec<Span<'_>> {
+        let mut spans: Vec<Span<'_>> = Vec::new();
+        let mut open = Vec::new();
+        for event in &self.events {
+            match &event.kind {
+                EventKind::ScopeBegin {
+                    name,
+                    data,
+                    color,
+                    fields,
+                    location,
+                } => {
+                    open.push(spans.len());
+                    spans.push(Span {
+                        name,
+                        data: data.as_deref(),
+                        color: *color,
+                        fields,
+                        location,
+                        begin_ns: event.time_ns,
+                        end_ns: event.time_ns,
+                        depth: open.len() - 1,
+                        parent: open.len().checked_sub(2).map(|parent| open[parent]),
+                    });
+                }
+                EventKind::ScopeEnd => {
+                    if let Some(index) = open.pop() {
+                        spans[index].end_ns = event.time_ns;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let last_ns = self.events.last().map(|event| event.time_ns).unwrap_or(0);
+        for index in open {
+            spans[index].end_ns = last_ns;
+        }
+
+        spans
+    }
+
+    /// Times at which `finish_frame!` was called on this thread
+    pub fn frame_ends_ns(&self) -> impl Iterator<Item = u64> + '_ {
+        self.events
+            .iter()
+            .filter(|event| matches!(event.kind, EventKind::FrameEnd))
+            .map(|event| event.time_ns)
+    }
+
+    /// Times at which `finish_frame!(name)` was called on this thread, with the name
+    pub fn named_frame_ends_ns(&self) -> impl Iterator<Item = (u64, &'static str)> + '_ {
+        self.events.iter().filter_map(|event| match event.kind {
+            EventKind::NamedFrameEnd { name } => Some((event.time_ns, name)),
+            _ => None,
+        })
+    }
+
+    /// Pairs up `frame_start!` and `frame_end!` calls with the same name, ordered by when they
+    /// began. Like [`spans`](Self::spans), ends without a begin are ignored and frames that never
+    /// ended are closed at the time of the thread's last event.
+    pub fn discontinuous_frames(&self) -> Vec<DiscontinuousFrame> {
+        let mut frames: Vec<DiscontinuousFrame> = Vec::new();
+        let mut open = Vec::new();
+        for event in &self.events {
+            match event.kind {
+                EventKind::DiscontinuousFrameBegin { name } => {
+                    open.push(frames.len());
+                    frames.push(DiscontinuousFrame {
+                        name,
+                        begin_ns: event.time_ns,
+                        end_ns: event.time_ns,
+                    });
+                }
+                EventKind::DiscontinuousFrameEnd { name } => {
+                    if let Some(position) =
+                        open.iter().rposition(|&index| frames[index].name == name)
+                    {
+                        let index = open.remove(position);
+                        frames[index].end_ns = event.time_ns;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let last_ns = self.events.last().map(|event| event.time_ns).unwrap_or(0);
+        for index in open {
+            frames[index].end_ns = last_ns;
+        }
+
+        frames
+    }
+
+    /// Markers recorded with `marker!` on this thread as (time, name, data, scope)
+    pub fn markers(&self) -> impl Iterator<Item = (u64, &str, Option<&str>, MarkerScope)> + '_ {
+        self.events.iter().filter_map(|event| match &event.kind {
+            EventKind::Marker {
+                name, data, scope, ..
+            } => Some((event.time_ns, &**name, data.as_deref(), *scope)),
+            _ => None,
+        })
+    }
+
+    /// Messages recorded with `message!` on this thread as (time, text, color)
+    pub fn messages(&self) -> impl Iterator<Item = (u64, &str, Option<u32>)> + '_ {
+        self.events.iter().filter_map(|event| match &event.kind {
+            EventKind::Message { text, color } => Some((event.time_ns, text.as_str(), *color)),
+            _ => None,
+        })
+    }
+
+    /// Values recorded with `plot!` on this thread as (time, name, value)
+    pub fn plot_values(&self) -> impl Iterator<Item = (u64, &'static str, f64)> + '_ {
+        self.events.iter().filter_map(|event| match event.kind {
+            EventKind::Plot { name, value } => Some((event.time_ns, name, value)),
+            _ => None,
+        })
+    }
+}
+
+impl Capture {
+    /// Returns true if no events were recorded
+    pub fn is_empty(&self) -> bool {
+        self.threads.iter().all(|thread| thread.events.is_empty())
+    }
+
+    /// Pairs up the begin and end events of tasks across all threads, ordered by when they began.
+    /// Ends without a begin are ignored and tasks that never ended are closed at the time of the
+    /// capture's last event.
+    pub fn tasks(&self) -> Vec<Task> {
+        let mut tasks: Vec<Task> = self
+            .threads
+            .iter()
+            .flat_map(|thread| {
+                thread
+                    .events
+                    .iter()
+                    .filter_map(move |event| match event.kind {
+                        EventKind::TaskBegin { id, name } => Some(Task {
+                            id,
+                            name,
+                            thread_id: thread.id,
+                            begin_ns: event.time_ns,
+                            end_ns: u64::MAX,
+                        }),
+                        _ => None,
+                    })
+            })
+            .collect();
+        tasks.sort_by_key(|task| task.begin_ns);
+
+        let index_by_id: BTreeMap<u64, usize> = tasks
+            .iter()
+            .enumerate()
+            .map(|(index, task)| (task.id, index))
+            .collect();
+        for thread in &self.threads {
+            for event in &thread.events {
+                if let EventKind::TaskEnd { id } = event.kind {
+                    if let Some(&index) = index_by_id.get(&id) {
+                        tasks[index].end_ns = event.time_ns;
+                    }
+                }
+            }
+        }
+
+        let last_ns = self.time_range_ns().map(|(_, max)| max).unwrap_or(0);
+        for task in &mut tasks {
+            if task.end_ns == u64::MAX {
+                task.end_ns = last_ns;
+            }
+        }
+
+        tasks
+    }
+
+    /// Pairs up each flow end with the begin of the same id, ordered by when the work started
+    /// running. A begin can have several ends, and ends without a begin are ignored. Ends recorded
+    /// outside of any scope are moved to the start of the next scope on their thread, so that they
+    /// always fall inside the scope they link to.
+    pub fn flows(&self) -> Vec<Flow> {
+        let mut begins = BTreeMap::new();
+        for thread in &self.threads {
+            for event in &thread.events {
+                if let EventKind::FlowBegin { id } = event.kind {
+                    begins.insert(id, (thread.id, event.time_ns));
+                }
+            }
+        }
+
+        let mut flows: Vec<Flow> = Vec::new();
+        for thread in &self.threads {
+            let mut depth = 0usize;
+            let mut waiting_for_scope: Vec<usize> = Vec::new();
+            for event in &thread.events {
+                match event.kind {
+                    EventKind::ScopeBegin { .. } => {
+                        depth += 1;
+                        for index in waiting_for_scope.drain(..) {
+                            flows[index].to_ns = event.time_ns;
+                        }
+                    }
+                    EventKind::ScopeEnd => depth = depth.saturating_sub(1),
+                    EventKind::FlowEnd { id } => {
+                        if let Some(&(from_thread_id, from_ns)) = begins.get(&id) {
+                            if depth == 0 {
+                                waiting_for_scope.push(flows.len());
+                            }
+                            flows.push(Flow {
+                                id,
+                                from_thread_id,
+                                from_ns,
+                                to_thread_id: thread.id,
+                                to_ns: event.time_ns,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        flows.sort_by_key(|flow| flow.to_ns);
+        flows
+    }
+
+    /// The time of the earliest and latest events, or `None` if there are no events
+    pub fn time_range_ns(&self) -> Option<(u64, u64)> {
+        let times = self
+            .threads
+            .iter()
+            .flat_map(|thread| {
+                thread
+                    .events
+                    .first()
+                    .into_iter()
+                    .chain(thread.events.last())
+            })
+            .map(|event| event.time_ns);
+        let (min, max) = times.fold((u64::MAX, 0), |(min, max), time| {
+            (min.min(time), max.max(time))
+        });
+        if min <= max {
+            Some((min, max))
+        } else {
+            None
+        }
+    }
+}
+
+/// Removes and returns everything recorded so far. Scopes that are still open will have their
+/// `ScopeEnd` in a later capture.
+pub fn drain() -> Capture {
+    let mut threads = lock(threads());
+    let capture = Capture {
+        threads: threads
+            .iter()
+            .map(|buffer| {
+                let mut buffer = lock(buffer);
+                ThreadCapture {
+                    id: buffer.id,
+                    name: buffer.name.clone(),
+                    events: core::mem::take(&mut buffer.events),
+                }
+            })
+            .collect(),
+    };
+
+    // Threads that have exited have nothing more to record
+    threads.retain(|buffer| Arc::strong_count(buffer) > 1);
+    capture
+}
+
+/// Returns a copy of everything recorded so far without removing it
+pub fn snapshot() -> Capture {
+    Capture {
+        threads: lock(threads())
+            .iter()
+            .map(|buffer| {
+                let buffer = lock(buffer);
+                ThreadCapture {
+                    id: buffer.id,
+                    name: buffer.name.clone(),
+                    events: buffer.events.clone(),
+                }
+            })
+            .collect(),
+    }
+}
+
+struct ThreadBuffer {
+    id: u64,
+    name: String,
+    events: Vec<Event>,
+}
+
+static THREADS: Lazy<Mutex<Vec<Arc<Mutex<ThreadBuffer>>>>> = Lazy::new();
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+static EPOCH: Lazy<Instant> = Lazy::new();
+
+fn threads() -> &'static Mutex<Vec<Arc<Mutex<ThreadBuffer>>>> {
+    THREADS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+std::thread_local! {
+    static BUFFER: Arc<Mutex<ThreadBuffer>> = {
+        let thread = std::thread::current();
+        let buffer = Arc::new(Mutex::new(ThreadBuffer {
+            id: NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed),
+            name: thread
+                .name()
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| std::format!("Thread {:?}", thread.id())),
+            events: Vec::new(),
+        }));
+        lock(threads()).push(buffer.clone());
+        buffer
+    };
+}
+
+// Recording must keep working if some other thread panicked while holding a lock
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+fn now_ns() -> u64 {
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+fn with_buffer(f: impl FnOnce(&mut ThreadBuffer)) {
+    // Ignore events recorded while the thread is being torn down
+    let _ = BUFFER.try_with(|buffer| f(&mut lock(buffer)));
+}
+
+fn record(kind: EventKind) {
+    let time_ns = now_ns();
+    with_buffer(|buffer| buffer.events.push(Event { time_ns, kind }));
+}
+
+//
+// Called from the macros in builtin_impl.rs but not intended for direct use
+//
+#[doc(hidden)]
+pub struct BuiltinScope {
+    // Scopes must end on the thread they began on
+    _not_send: PhantomData<*const ()>,
+}
+
+impl BuiltinScope {
+    #[doc(hidden)]
+    pub fn new(
+        name: &'static str,
+        data: Option<&str>,
+        color: Option<u32>,
+        location: ScopeLocation,
+    ) -> Self {
+        Self::begin(
+            Cow::Borrowed(name),
+            data.map(ToString::to_string),
+            color,
+            Vec::new(),
+            location,
+        )
+    }
+
+    #[doc(hidden)]
+    pub fn new_dynamic(
+        name: &str,
+        data: Option<&str>,
+        color: Option<u32>,
+        location: ScopeLocation,
+    ) -> Self {
+        Self::begin(
+            Cow::Owned(name.to_string()),
+            data.map(ToString::to_string),
+            color,
+            Vec::new(),
+            location,
+        )
+    }
+
+    #[doc(hidden)]
+    pub fn new_with_args(
+        name: &'static str,
+        data: fmt::Arguments<'_>,
+        location: ScopeLocation,
+    ) -> Self {
+        Self::begin(
+            Cow::Borrowed(name),
+            Some(data.to_string()),
+            None,
+            Vec::new(),
+            location,
+        )
+    }
+
+    #[doc(hidden)]
+    pub fn new_dynamic_with_args(
+        name: &str,
+        data: fmt::Arguments<'_>,
+        location: ScopeLocation,
+    ) -> Self {
+        Self::begin(
+            Cow::Owned(name.to_string()),
+            Some(data.to_string()),
+            None,
+            Vec::new(),
+            location,
+        )
+    }
+
+    #[doc(hidden)]
+    pub fn new_formatted(
+        name: fmt::Arguments<'_>,
+        location: ScopeLocation,
+    ) -> Self {
+        Self::begin(
+            Cow::Owned(name.to_string()),
+            None,
+            None,
+            Vec::new(),
+            location,
+        )
+    }
+
+    #[doc(hidden)]
+    pub fn new_with_fields(
+        name: &'static str,
+        fields: &[(&'static str, FieldValue<'_>)],
+        location: ScopeLocation,
+    ) -> Self {
+        Self::begin(
+            Cow::Borrowed(name),
+            None,
+            None,
+            own_fields(fields),
+            location,
+        )
+    }
+
+    #[doc(hidden)]
+    pub fn new_dynamic_with_fields(
+        name: &str,
+        fields: &[(&'static str, FieldValue<'_>)],
+        location: ScopeLocation,
+    ) -> Self {
+        Self::begin(
+            Cow::Owned(name.to_string()),
+            None,
+            None,
+            own_fields(fields),
+            location,
+        )
+    }
+
+    fn begin(
+        name: Cow<'static, str>,
+        data: Option<String>,
+        color: Option<u32>,
+        fields: Vec<(&'static str, OwnedFieldValue)>,
+        location: ScopeLocation,
+    ) -> Self {
+        record(EventKind::ScopeBegin {
+            name,
+            data,
+            color,
+            fields,
+            location,
+        });
+        BuiltinScope {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+fn own_fields(fields: &[(&'static str, FieldValue<'_>)]) -> Vec<(&'static str, OwnedFieldValue)> {
+    fields
+        .iter()
+        .map(|(key, value)| (*key, OwnedFieldValue::from(*value)))
+        .collect()
+}
+
+impl Drop for BuiltinScope {
+    fn drop(&mut self) {
+        record(EventKind::ScopeEnd);
+    }
+}
+
+#[doc(hidden)]
+pub fn set_thread_name(name: &str) {
+    with_buffer(|buffer| buffer.name = name.to_string());
+}
+
+#[doc(hidden)]
+pub fn finish_frame() {
+    record(EventKind::FrameEnd);
+}
+
+#[doc(hidden)]
+pub fn finish_named_frame(name: &'static str) {
+    record(EventKind::NamedFrameEnd { name });
+}
+
+#[doc(hidden)]
+pub fn frame_start(name: &'static str) {
+    record(EventKind::DiscontinuousFrameBegin { name });
+}
+
+#[doc(hidden)]
+pub fn frame_end(name: &'static str) {
+    record(EventKind::DiscontinuousFrameEnd { name });
+}
+
+#[doc(hidden)]
+pub fn task_begin(
+    id: u64,
+    name: &'static str,
+) {
+    record(EventKind::TaskBegin { id, name });
+}
+
+#[doc(hidden)]
+pub fn task_end(id: u64) {
+    record(EventKind::TaskEnd { id });
+}
+
+#[doc(hidden)]
+pub fn flow_begin(id: u64) {
+    record(EventKind::FlowBegin { id });
+}
+
+#[doc(hidden)]
+pub fn flow_end(id: u64) {
+    record(EventKind::FlowEnd { id });
+}
+
+#[doc(hidden)]
+pub fn plot(
+    name: &'static str,
+    value: f64,
+) {
+    record(EventKind::Plot { name, value });
+}
+
+#[doc(hidden)]
+pub fn marker(
+    name: &'static str,
+    data: Option<&str>,
+    scope: MarkerScope,
+    location: ScopeLocation,
+) {
+    record(EventKind::Marker {
+        name: Cow::Borrowed(name),
+        data: data.map(|data| data.to_string()),
+        scope,
+        location,
+    });
+}
+
+#[doc(hidden)]
+pub fn marker_dynamic(
+    name: &str,
+    data: Option<&str>,
+    scope: MarkerScope,
+    location: ScopeLocation,
+) {
+    record(EventKind::Marker {
+        name: Cow::Owned(name.to_string()),
+        data: data.map(|data| data.to_string()),
+        scope,
+        location,
+    });
+}
+
+#[doc(hidden)]
+pub fn message(
+    text: &str,
+    color: Option<u32>,
+) {
+    record(EventKind::Message {
+        text: text.to_string(),
+        color,
+    });
+}
+
+// A capture with a known layout for the exporter tests. On "Main Thread", "outer" runs from 1µs to
+// 9µs with "inner" from 2µs to 5µs inside it, and a message is sent at 3µs.
+#[cfg(test)]
+pub(crate) fn test_capture() -> Capture {
+    fn scope_begin(
+        time_ns: u64,
+        name: &'static str,
+        data: Option<&str>,
+        color: Option<u32>,
+        fields: Vec<(&'static str, OwnedFieldValue)>,
+    ) -> Event {
+        Event {
+            time_ns,
+            kind: EventKind::ScopeBegin {
+                name: Cow::Borrowed(name),
+                data: data.map(|data| data.to_string()),
+                color,
+                fields,
+                location: ScopeLocation {
+                    function_name: "app::update",
+                    file: "src/app.rs",
+                    line: 10,
+                },
+            },
+        }
+    }
+
+    fn scope_end(time_ns: u64) -> Event {
+        Event {
+            time_ns,
+            kind: EventKind::ScopeEnd,
+        }
+    }
+
+    Capture {
+        threads: std::vec![ThreadCapture {
+            id: 1,
+            name: "Main Thread".to_string(),
+            events: std::vec![
+                scope_begin(1000, "outer", Some("level1.dat"), None, Vec::new()),
+                scope_begin(
+                    2000,
+                    "inner",
+                    None,
+                    Some(0xC62828),
+                    std::vec![("bytes", OwnedFieldValue::U64(4096))],
+                ),
+                Event {
+                    time_ns: 3000,
+                    kind: EventKind::Message {
+                        text: "hello".to_string(),
+                        color: None,
+                    },
+                },
+                scope_end(5000),
+                scope_end(9000),
+            ],
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_on_the_current_thread() {
+        // Other tests may record at the same time, so only this thread's events are checked
+        crate::register_thread!("Recorder Test");
+        {
+            crate::scope!("outer");
+            crate::scope!("inner", "level1.dat");
+        }
+        crate::finish_frame!();
+
+        let find = |capture: Capture| {
+            capture
+                .threads
+                .into_iter()
+                .find(|thread| thread.name == "Recorder Test")
+                .unwrap()
+        };
+
+        let recorded = find(snapshot());
+        let kinds: Vec<_> = recorded.events.iter().map(|event| &event.kind).collect();
+        assert!(matches!(
+            kinds[..],
+            [
+                EventKind::ScopeBegin { .. },
+                EventKind::ScopeBegin { .. },
+                EventKind::ScopeEnd,
+                EventKind::ScopeEnd,
+                EventKind::FrameEnd,
+            ]
+        ));
+        assert!(recorded
+            .events
+            .windows(2)
+            .all(|pair| pair[0].time_ns <= pair[1].time_ns));
+
+        let spans = recorded.spans();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            (spans[0].name, spans[0].depth, spans[0].parent),
+            ("outer", 0, None)
+        );
+        assert_eq!(
+            (spans[1].name, spans[1].depth, spans[1].parent),
+            ("inner", 1, Some(0))
+        );
+        assert_eq!(spans[1].data, Some("level1.dat"));
+        assert!(spans[0].begin_ns <= spans[1].begin_ns && spans[1].end_ns <= spans[0].end_ns);
+        assert_eq!(recorded.frame_ends_ns().count(), 1);
+
+        // The snapshot left everything in place for drain to return, which then empties the buffer
+        let drained = find(drain());
+        assert_eq!(drained.id, recorded.id);
+        assert_eq!(drained.events.len(), recorded.events.len());
+        assert!(find(snapshot()).events.is_empty());
+    }
+}
@@ -0,0 +1,589 @@
+//! Writes captures as a native [Perfetto](https://perfetto.dev) trace, a stream of `TracePacket`
+//! protobufs. This is much more compact than JSON for long captures and can be opened in
+//! [Perfetto UI](https://ui.perfetto.dev) or queried with trace_processor.
+//!
+//! ```
+//! profiling::register_thread!("Main Thread");
+//! {
+//!     profiling::scope!("Update");
+//! }
+//! profiling::finish_frame!();
+//!
+//! let mut trace = Vec::new();
+//! profiling::builtin::perfetto::write(&profiling::builtin::drain(), &mut trace).unwrap();
+//! ```
+
+use super::protobuf::Message;
+use super::{Capture, EventKind, OwnedFieldValue};
+use crate::{AsFieldValue, FieldValue, MarkerScope, ScopeLocation};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+// Field numbers from protos/perfetto/trace/trace_packet.proto and friends
+mod trace {
+    pub const PACKET: u32 = 1;
+}
+
+mod trace_packet {
+    pub const TIMESTAMP: u32 = 8;
+    pub const TRUSTED_PACKET_SEQUENCE_ID: u32 = 10;
+    pub const TRACK_EVENT: u32 = 11;
+    pub const SEQUENCE_FLAGS: u32 = 13;
+    pub const TRACK_DESCRIPTOR: u32 = 60;
+
+    pub const SEQ_INCREMENTAL_STATE_CLEARED: u64 = 1;
+}
+
+mod track_descriptor {
+    pub const UUID: u32 = 1;
+    pub const NAME: u32 = 2;
+    pub const PROCESS: u32 = 3;
+    pub const THREAD: u32 = 4;
+    pub const PARENT_UUID: u32 = 5;
+    pub const COUNTER: u32 = 8;
+}
+
+mod process_descriptor {
+    pub const PID: u32 = 1;
+}
+
+mod thread_descriptor {
+    pub const PID: u32 = 1;
+    pub const TID: u32 = 2;
+    pub const THREAD_NAME: u32 = 5;
+}
+
+mod track_event {
+    pub const DEBUG_ANNOTATIONS: u32 = 4;
+    pub const TYPE: u32 = 9;
+    pub const TRACK_UUID: u32 = 11;
+    pub const NAME: u32 = 23;
+    pub const SOURCE_LOCATION: u32 = 33;
+    pub const DOUBLE_COUNTER_VALUE: u32 = 44;
+    pub const FLOW_IDS: u32 = 47;
+    pub const TERMINATING_FLOW_IDS: u32 = 48;
+
+    pub const TYPE_SLICE_BEGIN: u64 = 1;
+    pub const TYPE_SLICE_END: u64 = 2;
+    pub const TYPE_INSTANT: u64 = 3;
+    pub const TYPE_COUNTER: u64 = 4;
+}
+
+mod source_location {
+    pub const FILE_NAME: u32 = 2;
+    pub const FUNCTION_NAME: u32 = 3;
+    pub const LINE_NUMBER: u32 = 4;
+}
+
+mod debug_annotation {
+    pub const BOOL_VALUE: u32 = 2;
+    pub const UINT_VALUE: u32 = 3;
+    pub const INT_VALUE: u32 = 4;
+    pub const DOUBLE_VALUE: u32 = 5;
+    pub const STRING_VALUE: u32 = 6;
+    pub const NAME: u32 = 10;
+}
+
+// Everything is written on a single packet sequence
+const SEQUENCE_ID: u64 = 1;
+
+const PROCESS_TRACK_UUID: u64 = 1;
+const FRAME_TRACK_UUID: u64 = 2;
+const GLOBAL_TRACK_UUID: u64 = 3;
+
+fn thread_track_uuid(thread_id: u64) -> u64 {
+    // Keep clear of the fixed uuids above
+    0x1000 + thread_id
+}
+
+// Keep clear of the thread tracks
+const FIRST_COUNTER_TRACK_UUID: u64 = 1 << 32;
+const FIRST_FRAME_SET_TRACK_UUID: u64 = 2 << 32;
+const FIRST_TASK_TRACK_UUID: u64 = 3 << 32;
+
+/// Writes a track descriptor for the process, one for each thread, one for frame markers, one for
+/// global markers, one for each named frame set, one for each task and a counter track for each
+/// plot, followed by these events:
+///
+/// - slice begin/end events for scopes, discontinuous frames and the lifetimes of tasks
+/// - instant events for `finish_frame!`, `marker!` and `message!`
+/// - pairs of instant events connected by a flow for work handed off between threads
+/// - counter events for `plot!`
+///
+/// Markers go on the track of their thread, the process or the global track depending on their
+/// scope. The `data` of a scope or marker is written as a debug annotation.
+pub fn write(
+    capture: &Capture,
+    writer: impl Write,
+) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+    let pid = std::process::id() as u64;
+
+    let mut packet = Message::new();
+    packet
+        .uint64(trace_packet::TRUSTED_PACKET_SEQUENCE_ID, SEQUENCE_ID)
+        .uint64(
+            trace_packet::SEQUENCE_FLAGS,
+            trace_packet::SEQ_INCREMENTAL_STATE_CLEARED,
+        )
+        .message(trace_packet::TRACK_DESCRIPTOR, |track| {
+            track
+                .uint64(track_descriptor::UUID, PROCESS_TRACK_UUID)
+                .message(track_descriptor::PROCESS, |process| {
+                    process.uint64(process_descriptor::PID, pid);
+                });
+        });
+    write_packet(&mut writer, packet)?;
+
+    let mut packet = Message::new();
+    packet
+        .uint64(trace_packet::TRUSTED_PACKET_SEQUENCE_ID, SEQUENCE_ID)
+        .message(trace_packet::TRACK_DESCRIPTOR, |track| {
+            track
+                .uint64(track_descriptor::UUID, FRAME_TRACK_UUID)
+                .uint64(track_descriptor::PARENT_UUID, PROCESS_TRACK_UUID)
+                .string(track_descriptor::NAME, "Frames");
+        });
+    write_packet(&mut writer, packet)?;
+
+    // Global markers go on a track that doesn't belong to this process
+    let mut packet = Message::new();
+    packet
+        .uint64(trace_packet::TRUSTED_PACKET_SEQUENCE_ID, SEQUENCE_ID)
+        .message(trace_packet::TRACK_DESCRIPTOR, |track| {
+            track
+                .uint64(track_descriptor::UUID, GLOBAL_TRACK_UUID)
+                .string(track_descriptor::NAME, "Global Markers");
+        });
+    write_packet(&mut writer, packet)?;
+
+    for thread in &capture.threads {
+        let mut packet = Message::new();
+        packet
+            .uint64(trace_packet::TRUSTED_PACKET_SEQUENCE_ID, SEQUENCE_ID)
+            .message(trace_packet::TRACK_DESCRIPTOR, |track| {
+                track
+                    .uint64(track_descriptor::UUID, thread_track_uuid(thread.id))
+                    .uint64(track_descriptor::PARENT_UUID, PROCESS_TRACK_UUID)
+                    .message(track_descriptor::THREAD, |descriptor| {
+                        descriptor
+                            .uint64(thread_descriptor::PID, pid)
+                            .uint64(thread_descriptor::TID, thread.id)
+                            .string(thread_descriptor::THREAD_NAME, &thread.name);
+                    });
+            });
+        write_packet(&mut writer, packet)?;
+    }
+
+    // Plots are per process, so a plot written from several threads is a single track
+    let mut counter_track_uuids = BTreeMap::new();
+    for thread in &capture.threads {
+        for (_, name, _) in thread.plot_values() {
+            let next_uuid = FIRST_COUNTER_TRACK_UUID + counter_track_uuids.len() as u64;
+            counter_track_uuids.entry(name).or_insert(next_uuid);
+        }
+    }
+
+    for (name, uuid) in &counter_track_uuids {
+        let mut packet = Message::new();
+        packet
+            .uint64(trace_packet::TRUSTED_PACKET_SEQUENCE_ID, SEQUENCE_ID)
+            .message(trace_packet::TRACK_DESCRIPTOR, |track| {
+                track
+                    .uint64(track_descriptor::UUID, *uuid)
+                    .uint64(track_descriptor::PARENT_UUID, PROCESS_TRACK_UUID)
+                    .string(track_descriptor::NAME, name)
+                    .message(track_descriptor::COUNTER, |_| {});
+            });
+        write_packet(&mut writer, packet)?;
+    }
+
+    // Like the main frames, each named frame set gets a track of its own
+    let mut frame_set_track_uuids = BTreeMap::new();
+    for thread in &capture.threads {
+        let names = thread.named_frame_ends_ns().map(|(_, name)| name).chain(
+            thread
+                .discontinuous_frames()
+                .into_iter()
+                .map(|frame| frame.name),
+        );
+        for name in names {
+            let next_uuid = FIRST_FRAME_SET_TRACK_UUID + frame_set_track_uuids.len() as u64;
+            frame_set_track_uuids.entry(name).or_insert(next_uuid);
+        }
+    }
+
+    for (name, uuid) in &frame_set_track_uuids {
+        let mut packet = Message::new();
+        packet
+            .uint64(trace_packet::TRUSTED_PACKET_SEQUENCE_ID, SEQUENCE_ID)
+            .message(trace_packet::TRACK_DESCRIPTOR, |track| {
+                track
+                    .uint64(track_descriptor::UUID, *uuid)
+                    .uint64(track_descriptor::PARENT_UUID, PROCESS_TRACK_UUID)
+                    .string(track_descriptor::NAME, name);
+            });
+        write_packet(&mut writer, packet)?;
+    }
+
+    for thread in &capture.threads {
+        let track_uuid = thread_track_uuid(thread.id);
+
+        // Ends without a begin come from scopes that were open when an earlier capture was drained
+        let mut depth = 0;
+        for event in &thread.events {
+            match &event.kind {
+                EventKind::ScopeBegin {
+                    name,
+                    data,
+                    fields,
+                    location,
+                    ..
+                } => {
+                    depth += 1;
+                    write_track_event(&mut writer, event.time_ns, |track_event| {
+                        track_event
+                            .uint64(track_event::TYPE, track_event::TYPE_SLICE_BEGIN)
+                            .uint64(track_event::TRACK_UUID, track_uuid)
+                            .string(track_event::NAME, name);
+                        write_location_and_data(track_event, location, data.as_deref());
+                        write_fields(track_event, fields);
+                    })?;
+                }
+                EventKind::ScopeEnd => {
+                    if depth > 0 {
+                        depth -= 1;
+                        write_slice_end(&mut writer, event.time_ns, track_uuid)?;
+                    }
+                }
+                EventKind::FrameEnd => {
+                    write_track_event(&mut writer, event.time_ns, |track_event| {
+                        track_event
+                            .uint64(track_event::TYPE, track_event::TYPE_INSTANT)
+                            .uint64(track_event::TRACK_UUID, FRAME_TRACK_UUID)
+                            .string(track_event::NAME, "Frame");
+                    })?;
+                }
+                EventKind::NamedFrameEnd { name } => {
+                    write_track_event(&mut writer, event.time_ns, |track_event| {
+                        track_event
+                            .uint64(track_event::TYPE, track_event::TYPE_INSTANT)
+                            .uint64(track_event::TRACK_UUID, frame_set_track_uuids[name])
+                            .string(track_event::NAME, "Frame");
+                    })?;
+                }
+                // Written below once they are paired up
+                EventKind::DiscontinuousFrameBegin { .. }
+                | EventKind::DiscontinuousFrameEnd { .. } => {}
+                EventKind::TaskBegin { .. } | EventKind::TaskEnd { .. } => {}
+                EventKind::FlowBegin { .. } | EventKind::FlowEnd { .. } => {}
+                EventKind::Marker {
+                    name,
+                    data,
+                    scope,
+                    location,
+                } => {
+                    let marker_track_uuid = match scope {
+                        MarkerScope::Thread => track_uuid,
+                        MarkerScope::Process => PROCESS_TRACK_UUID,
+                        MarkerScope::Global => GLOBAL_TRACK_UUID,
+                    };
+                    write_track_event(&mut writer, event.time_ns, |track_event| {
+                        track_event
+                            .uint64(track_event::TYPE, track_event::TYPE_INSTANT)
+                            .uint64(track_event::TRACK_UUID, marker_track_uuid)
+                            .string(track_event::NAME, name);
+                        write_location_and_data(track_event, location, data.as_deref());
+                    })?;
+                }
+                EventKind::Message { text, .. } => {
+                    write_track_event(&mut writer, event.time_ns, |track_event| {
+                        track_event
+                            .uint64(track_event::TYPE, track_event::TYPE_INSTANT)
+                            .uint64(track_event::TRACK_UUID, track_uuid)
+                            .string(track_event::NAME, text);
+                    })?;
+                }
+                EventKind::Plot { name, value } => {
+                    write_track_event(&mut writer, event.time_ns, |track_event| {
+                        track_event
+                            .uint64(track_event::TYPE, track_event::TYPE_COUNTER)
+                            .uint64(track_event::TRACK_UUID, counter_track_uuids[name])
+                            .double(track_event::DOUBLE_COUNTER_VALUE, *value);
+                    })?;
+                }
+            }
+        }
+
+        // Close scopes that were still open when the capture was taken
+        let last_ns = thread.events.last().map(|event| event.time_ns).unwrap_or(0);
+        for _ in 0..depth {
+            write_slice_end(&mut writer, last_ns, track_uuid)?;
+        }
+
+        for frame in thread.discontinuous_frames() {
+            let frame_set_track_uuid = frame_set_track_uuids[frame.name];
+            write_track_event(&mut writer, frame.begin_ns, |track_event| {
+                track_event
+                    .uint64(track_event::TYPE, track_event::TYPE_SLICE_BEGIN)
+                    .uint64(track_event::TRACK_UUID, frame_set_track_uuid)
+                    .string(track_event::NAME, frame.name);
+            })?;
+            write_slice_end(&mut writer, frame.end_ns, frame_set_track_uuid)?;
+        }
+    }
+
+    // Tasks move between threads, so each one gets a track of its own
+    for task in capture.tasks() {
+        let task_track_uuid = FIRST_TASK_TRACK_UUID + task.id;
+        let mut packet = Message::new();
+        packet
+            .uint64(trace_packet::TRUSTED_PACKET_SEQUENCE_ID, SEQUENCE_ID)
+            .message(trace_packet::TRACK_DESCRIPTOR, |track| {
+                track
+                    .uint64(track_descriptor::UUID, task_track_uuid)
+                    .uint64(track_descriptor::PARENT_UUID, PROCESS_TRACK_UUID)
+                    .string(track_descriptor::NAME, task.name);
+            });
+        write_packet(&mut writer, packet)?;
+
+        write_track_event(&mut writer, task.begin_ns, |track_event| {
+            track_event
+                .uint64(track_event::TYPE, track_event::TYPE_SLICE_BEGIN)
+                .uint64(track_event::TRACK_UUID, task_track_uuid)
+                .string(track_event::NAME, task.name);
+        })?;
+        write_slice_end(&mut writer, task.end_ns, task_track_uuid)?;
+    }
+
+    // Each end of a flow gets its own id, since a flow with several ends would otherwise be drawn
+    // as a chain. The instant events are needed because flows connect events, not points in time.
+    for (index, flow) in capture.flows().iter().enumerate() {
+        let flow_id = index as u64 + 1;
+        write_track_event(&mut writer, flow.from_ns, |track_event| {
+            track_event
+                .uint64(track_event::TYPE, track_event::TYPE_INSTANT)
+                .uint64(
+                    track_event::TRACK_UUID,
+                    thread_track_uuid(flow.from_thread_id),
+                )
+                .string(track_event::NAME, "Handoff")
+                .fixed64(track_event::FLOW_IDS, flow_id);
+        })?;
+        write_track_event(&mut writer, flow.to_ns, |track_event| {
+            track_event
+                .uint64(track_event::TYPE, track_event::TYPE_INSTANT)
+                .uint64(
+                    track_event::TRACK_UUID,
+                    thread_track_uuid(flow.to_thread_id),
+                )
+                .string(track_event::NAME, "Handoff")
+                .fixed64(track_event::TERMINATING_FLOW_IDS, flow_id);
+        })?;
+    }
+
+    writer.flush()
+}
+
+fn write_location_and_data(
+    track_event: &mut Message,
+    location: &ScopeLocation,
+    data: Option<&str>,
+) {
+    track_event.message(track_event::SOURCE_LOCATION, |source| {
+        source
+            .string(source_location::FILE_NAME, location.file)
+            .string(source_location::FUNCTION_NAME, location.function_name)
+            .uint64(source_location::LINE_NUMBER, location.line.into());
+    });
+    if let Some(data) = data {
+        track_event.message(track_event::DEBUG_ANNOTATIONS, |annotation| {
+            annotation
+                .string(debug_annotation::NAME, "data")
+                .string(debug_annotation::STRING_VALUE, data);
+        });
+    }
+}
+
+fn write_fields(
+    track_event: &mut Message,
+    fields: &[(&'static str, OwnedFieldValue)],
+) {
+    for (key, value) in fields {
+        track_event.message(track_event::DEBUG_ANNOTATIONS, |annotation| {
+            annotation.string(debug_annotation::NAME, key);
+            match value.as_field_value() {
+                // int64 fields are encoded as the two's complement varint
+                FieldValue::I64(value) => {
+                    annotation.uint64(debug_annotation::INT_VALUE, value as u64)
+                }
+                FieldValue::U64(value) => annotation.uint64(debug_annotation::UINT_VALUE, value),
+                FieldValue::F64(value) => annotation.double(debug_annotation::DOUBLE_VALUE, value),
+                FieldValue::Bool(value) => {
+                    annotation.uint64(debug_annotation::BOOL_VALUE, value.into())
+                }
+                FieldValue::Str(value) => annotation.string(debug_annotation::STRING_VALUE, value),
+            };
+        });
+    }
+}
+
+fn write_slice_end(
+    writer: &mut impl Write,
+    time_ns: u64,
+    track_uuid: u64,
+) -> io::Result<()> {
+    write_track_event(writer, time_ns, |track_event| {
+        track_event
+            .uint64(track_event::TYPE, track_event::TYPE_SLICE_END)
+            .uint64(track_event::TRACK_UUID, track_uuid);
+    })
+}
+
+fn write_track_event(
+    writer: &mut impl Write,
+    time_ns: u64,
+    build: impl FnOnce(&mut Message),
+) -> io::Result<()> {
+    let mut packet = Message::new();
+    packet
+        .uint64(trace_packet::TIMESTAMP, time_ns)
+        .uint64(trace_packet::TRUSTED_PACKET_SEQUENCE_ID, SEQUENCE_ID)
+        .message(trace_packet::TRACK_EVENT, build);
+    write_packet(writer, packet)
+}
+
+// A trace file is a serialized `Trace` message, which is just its packets one after another
+fn write_packet(
+    writer: &mut impl Write,
+    packet: Message,
+) -> io::Result<()> {
+    let mut trace = Message::new();
+    trace.bytes(trace::PACKET, &packet.into_bytes());
+    writer.write_all(&trace.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::protobuf::reader::{decode, message, Field::Bytes, Field::Varint};
+    use crate::builtin::test_capture;
+    use std::vec::Vec;
+
+    // Field numbers are spelled out rather than taken from the modules above, so that a wrong
+    // number there shows up here
+    #[test]
+    fn track_descriptors_and_events() {
+        let mut trace = Vec::new();
+        super::write(&test_capture(), &mut trace).unwrap();
+        let pid = std::process::id() as u64;
+        let thread_track_uuid = 0x1001;
+
+        // Trace.packet = 1
+        let packets: Vec<_> = decode(&trace)
+            .into_iter()
+            .map(|(field, packet)| match (field, packet) {
+                (1, Bytes(packet)) => decode(packet),
+                other => panic!("expected a packet, found {:?}", other),
+            })
+            .collect();
+        assert_eq!(packets.len(), 9);
+
+        // Everything is on one sequence (trusted_packet_sequence_id = 10), and the first packet
+        // clears its incremental state (sequence_flags = 13)
+        for packet in &packets {
+            assert!(packet.contains(&(10, Varint(1))));
+        }
+        assert!(packets[0].contains(&(13, Varint(1))));
+
+        // TracePacket.track_descriptor = 60
+        // TrackDescriptor: uuid = 1, name = 2, process = 3, thread = 4, parent_uuid = 5
+        let process = message(&packets[0], 60);
+        assert_eq!(process[0], (1, Varint(1)));
+        assert_eq!(message(&process, 3), [(1, Varint(pid))]);
+        assert_eq!(
+            message(&packets[1], 60),
+            [(1, Varint(2)), (5, Varint(1)), (2, Bytes(b"Frames"))]
+        );
+        assert_eq!(
+            message(&packets[2], 60),
+            [(1, Varint(3)), (2, Bytes(b"Global Markers"))]
+        );
+        let thread = message(&packets[3], 60);
+        assert_eq!(
+            thread[..2],
+            [(1, Varint(thread_track_uuid)), (5, Varint(1))]
+        );
+        assert_eq!(
+            message(&thread, 4),
+            [(1, Varint(pid)), (2, Varint(1)), (5, Bytes(b"Main Thread"))]
+        );
+
+        // TracePacket.timestamp = 8, TracePacket.track_event = 11
+        let timestamps: Vec<_> = packets[4..].iter().map(|packet| &packet[0]).collect();
+        assert_eq!(
+            timestamps,
+            [
+                &(8, Varint(1000)),
+                &(8, Varint(2000)),
+                &(8, Varint(3000)),
+                &(8, Varint(5000)),
+                &(8, Varint(9000))
+            ]
+        );
+
+        // TrackEvent: type = 9 (slice begin = 1, slice end = 2, instant = 3), track_uuid = 11,
+        // name = 23, debug_annotations = 4, source_location = 33
+        // SourceLocation: file_name = 2, function_name = 3, line_number = 4
+        // DebugAnnotation: name = 10, uint_value = 3, string_value = 6
+        let source_location = [
+            (2, Bytes(b"src/app.rs")),
+            (3, Bytes(b"app::update")),
+            (4, Varint(10)),
+        ];
+
+        let outer = message(&packets[4], 11);
+        assert_eq!(
+            outer[..3],
+            [
+                (9, Varint(1)),
+                (11, Varint(thread_track_uuid)),
+                (23, Bytes(b"outer"))
+            ]
+        );
+        assert_eq!(message(&outer, 33), source_location);
+        assert_eq!(
+            message(&outer, 4),
+            [(10, Bytes(b"data")), (6, Bytes(b"level1.dat"))]
+        );
+
+        let inner = message(&packets[5], 11);
+        assert_eq!(
+            inner[..3],
+            [
+                (9, Varint(1)),
+                (11, Varint(thread_track_uuid)),
+                (23, Bytes(b"inner"))
+            ]
+        );
+        assert_eq!(message(&inner, 33), source_location);
+        assert_eq!(
+            message(&inner, 4),
+            [(10, Bytes(b"bytes")), (3, Varint(4096))]
+        );
+
+        assert_eq!(
+            message(&packets[6], 11),
+            [
+                (9, Varint(3)),
+                (11, Varint(thread_track_uuid)),
+                (23, Bytes(b"hello"))
+            ]
+        );
+        for packet in &packets[7..] {
+            assert_eq!(
+                message(packet, 11),
+                [(9, Varint(2)), (11, Varint(thread_track_uuid))]
+            );
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Writes captures as a [pprof](https://github.com/google/pprof) `profile.proto`, for `pprof` and
+//! the continuous profiling services that ingest it. The output is not compressed, which pprof
+//! accepts as-is.
+//!
+//! ```
+//! profiling::register_thread!("Main Thread");
+//! {
+//!     profiling::scope!("Update");
+//!     profiling::scope!("Physics");
+//! }
+//!
+//! let mut profile = Vec::new();
+//! profiling::builtin::pprof::write(&profiling::builtin::drain(), &mut profile).unwrap();
+//! ```
+
+use super::protobuf::Message;
+use super::Capture;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::vec::Vec;
+
+// Field numbers from proto/profile.proto
+mod profile {
+    pub const SAMPLE_TYPE: u32 = 1;
+    pub const SAMPLE: u32 = 2;
+    pub const LOCATION: u32 = 4;
+    pub const FUNCTION: u32 = 5;
+    pub const STRING_TABLE: u32 = 6;
+    pub const DURATION_NANOS: u32 = 10;
+}
+
+mod value_type {
+    pub const TYPE: u32 = 1;
+    pub const UNIT: u32 = 2;
+}
+
+mod sample {
+    pub const LOCATION_ID: u32 = 1;
+    pub const VALUE: u32 = 2;
+    pub const LABEL: u32 = 3;
+}
+
+mod label {
+    pub const KEY: u32 = 1;
+    pub const STR: u32 = 2;
+}
+
+mod location {
+    pub const ID: u32 = 1;
+    pub const LINE: u32 = 4;
+}
+
+mod line {
+    pub const FUNCTION_ID: u32 = 1;
+    pub const LINE: u32 = 2;
+}
+
+mod function {
+    pub const ID: u32 = 1;
+    pub const NAME: u32 = 2;
+    pub const SYSTEM_NAME: u32 = 3;
+    pub const FILENAME: u32 = 4;
+    pub const START_LINE: u32 = 5;
+}
+
+// Strings are referred to by their index in the string table, which must start with ""
+#[derive(Default)]
+struct StringTable<'a> {
+    strings: Vec<&'a str>,
+    indices: HashMap<&'a str, u64>,
+}
+
+impl<'a> StringTable<'a> {
+    fn index(
+        &mut self,
+        string: &'a str,
+    ) -> u64 {
+        if self.strings.is_empty() {
+            self.strings.push("");
+            self.indices.insert("", 0);
+        }
+
+        let strings = &mut self.strings;
+        *self.indices.entry(string).or_insert_with(|| {
+            strings.push(string);
+            strings.len() as u64 - 1
+        })
+    }
+}
+
+/// Writes one sample per unique stack of nested scopes on each thread, valued by the time spent in
+/// the innermost scope excluding nested scopes. Each distinct scope name and location becomes a
+/// `Function` with its file and line, referenced by a `Location` of the same id. Samples are
+/// labelled with the name of the thread they were recorded on. Frame ends and scope `data` are not
+/// written.
+pub fn write(
+    capture: &Capture,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let mut strings = StringTable::default();
+    let mut functions = Vec::new();
+    let mut function_ids = HashMap::new();
+    // Keyed by thread name and leaf-first stack of location ids
+    let mut samples = BTreeMap::<(&str, Vec<u64>), u64>::new();
+
+    for thread in &capture.threads {
+        let spans = thread.spans();
+
+        let mut child_ns = std::vec![0; spans.len()];
+        for span in &spans {
+            if let Some(parent) = span.parent {
+                child_ns[parent] += span.end_ns - span.begin_ns;
+            }
+        }
+
+        // Spans are ordered by when they began, so a parent's stack is built before its children's
+        let mut stacks: Vec<Vec<u64>> = Vec::with_capacity(spans.len());
+        for (index, span) in spans.iter().enumerate() {
+            let key = (span.name, span.location.file, span.location.line);
+            let id = *function_ids.entry(key).or_insert_with(|| {
+                functions.push(key);
+                functions.len() as u64
+            });
+
+            let mut stack = std::vec![id];
+            if let Some(parent) = span.parent {
+                stack.extend_from_slice(&stacks[parent]);
+            }
+
+            let value = (span.end_ns - span.begin_ns).saturating_sub(child_ns[index]);
+            if value > 0 {
+                *samples
+                    .entry((thread.name.as_str(), stack.clone()))
+                    .or_default() += value;
+            }
+            stacks.push(stack);
+        }
+    }
+
+    let mut profile = Message::new();
+    let time = strings.index("time");
+    let nanoseconds = strings.index("nanoseconds");
+    profile.message(profile::SAMPLE_TYPE, |sample_type| {
+        sample_type
+            .uint64(value_type::TYPE, time)
+            .uint64(value_type::UNIT, nanoseconds);
+    });
+
+    let thread_key = strings.index("thread");
+    for ((thread_name, stack), value) in samples {
+        let thread_name = strings.index(thread_name);
+        profile.message(profile::SAMPLE, |sample| {
+            sample
+                .packed_uint64(sample::LOCATION_ID, stack)
+                .packed_uint64(sample::VALUE, [value])
+                .message(sample::LABEL, |label| {
+                    label
+                        .uint64(label::KEY, thread_key)
+                        .uint64(label::STR, thread_name);
+                });
+        });
+    }
+
+    for (index, (name, file, line)) in functions.into_iter().enumerate() {
+        let id = index as u64 + 1;
+        let name = strings.index(name);
+        let file = strings.index(file);
+        profile
+            .message(profile::FUNCTION, |function| {
+                function
+                    .uint64(function::ID, id)
+                    .uint64(function::NAME, name)
+                    .uint64(function::SYSTEM_NAME, name)
+                    .uint64(function::FILENAME, file)
+                    .uint64(function::START_LINE, line.into());
+            })
+            .message(profile::LOCATION, |location| {
+                location
+                    .uint64(location::ID, id)
+                    .message(location::LINE, |location_line| {
+                        location_line
+                            .uint64(line::FUNCTION_ID, id)
+                            .uint64(line::LINE, line.into());
+                    });
+            });
+    }
+
+    if let Some((begin_ns, end_ns)) = capture.time_range_ns() {
+        profile.uint64(profile::DURATION_NANOS, end_ns - begin_ns);
+    }
+
+    for string in &strings.strings {
+        profile.string(profile::STRING_TABLE, string);
+    }
+
+    writer.write_all(&profile.into_bytes())?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::protobuf::reader::{decode, message, packed, Field, Field::Bytes};
+    use crate::builtin::test_capture;
+    use std::vec::Vec;
+
+    fn varint(field: &(u32, Field<'_>)) -> (u32, u64) {
+        match field {
+            (number, Field::Varint(value)) => (*number, *value),
+            other => panic!("expected a varint, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn samples_locations_functions_and_strings() {
+        let mut output = Vec::new();
+        super::write(&test_capture(), &mut output).unwrap();
+
+        // Not gzipped, so there is nothing to decompress before decoding
+        assert_ne!(output[..2], [0x1f, 0x8b]);
+        let profile = decode(&output);
+
+        // Profile.string_table = 6
+        let strings: Vec<&[u8]> = profile
+            .iter()
+            .filter_map(|field| match field {
+                (6, Bytes(string)) => Some(*string),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            strings,
+            [
+                &b""[..],
+                b"time",
+                b"nanoseconds",
+                b"thread",
+                b"Main Thread",
+                b"outer",
+                b"src/app.rs",
+                b"inner"
+            ]
+        );
+
+        // Profile: sample_type = 1, sample = 2, location = 4, function = 5, duration_nanos = 10
+        let numbers: Vec<u32> = profile.iter().map(|(number, _)| *number).collect();
+        assert_eq!(numbers[..8], [1, 2, 2, 5, 4, 5, 4, 10]);
+
+        // ValueType: type = 1, unit = 2
+        let sample_type: Vec<_> = decode_at(&profile, 0).iter().map(varint).collect();
+        assert_eq!(sample_type, [(1, 1), (2, 2)]);
+
+        // Sample: location_id = 1 and value = 2, both packed, label = 3
+        // Label: key = 1, str = 2
+        // Stacks are leaf first, and the value is the time spent in the leaf excluding nested scopes
+        for (index, stack, value) in [(1, std::vec![1], 5000), (2, std::vec![2, 1], 3000)] {
+            let sample = decode_at(&profile, index);
+            assert_eq!(sample.len(), 3);
+            match &sample[..2] {
+                [(1, Bytes(location_ids)), (2, Bytes(values))] => {
+                    assert_eq!(packed(location_ids), stack);
+                    assert_eq!(packed(values), [value]);
+                }
+                other => panic!("unexpected sample fields {:?}", other),
+            }
+            let label: Vec<_> = message(&sample, 3).iter().map(varint).collect();
+            assert_eq!(label, [(1, 3), (2, 4)]);
+        }
+
+        // Function: id = 1, name = 2, system_name = 3, filename = 4, start_line = 5
+        // Location: id = 1, line = 4
+        // Line: function_id = 1, line = 2
+        for (index, id, name) in [(3, 1, 5), (5, 2, 7)] {
+            let function: Vec<_> = decode_at(&profile, index).iter().map(varint).collect();
+            assert_eq!(function, [(1, id), (2, name), (3, name), (4, 6), (5, 10)]);
+
+            let location = decode_at(&profile, index + 1);
+            assert_eq!(varint(&location[0]), (1, id));
+            let line: Vec<_> = message(&location, 4).iter().map(varint).collect();
+            assert_eq!(line, [(1, id), (2, 10)]);
+        }
+
+        assert_eq!(varint(&profile[7]), (10, 8000));
+    }
+
+    fn decode_at<'a>(
+        fields: &[(u32, Field<'a>)],
+        index: usize,
+    ) -> Vec<(u32, Field<'a>)> {
+        match &fields[index] {
+            (_, Bytes(bytes)) => decode(bytes),
+            other => panic!("expected a message, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+// Just enough protobuf encoding to write the binary trace formats without pulling in prost
+
+use std::vec::Vec;
+
+const VARINT: u32 = 0;
+const FIXED64: u32 = 1;
+const LENGTH_DELIMITED: u32 = 2;
+
+/// An encoded protobuf message. Fields are appended in the order they are written.
+#[derive(Default)]
+pub(crate) struct Message {
+    buffer: Vec<u8>,
+}
+
+impl Message {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    fn varint(
+        &mut self,
+        mut value: u64,
+    ) {
+        while value >= 0x80 {
+            self.buffer.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buffer.push(value as u8);
+    }
+
+    fn key(
+        &mut self,
+        field: u32,
+        wire_type: u32,
+    ) {
+        self.varint(u64::from(field << 3 | wire_type));
+    }
+
+    // Also used for int64 fields, which are encoded the same way when the value isn't negative
+    pub(crate) fn uint64(
+        &mut self,
+        field: u32,
+        value: u64,
+    ) -> &mut Self {
+        self.key(field, VARINT);
+        self.varint(value);
+        self
+    }
+
+    pub(crate) fn double(
+        &mut self,
+        field: u32,
+        value: f64,
+    ) -> &mut Self {
+        self.key(field, FIXED64);
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn fixed64(
+        &mut self,
+        field: u32,
+        value: u64,
+    ) -> &mut Self {
+        self.key(field, FIXED64);
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn packed_uint64(
+        &mut self,
+        field: u32,
+        values: impl IntoIterator<Item = u64>,
+    ) -> &mut Self {
+        let mut packed = Message::new();
+        for value in values {
+            packed.varint(value);
+        }
+        self.bytes(field, &packed.buffer)
+    }
+
+    pub(crate) fn bytes(
+        &mut self,
+        field: u32,
+        value: &[u8],
+    ) -> &mut Self {
+        self.key(field, LENGTH_DELIMITED);
+        self.varint(value.len() as u64);
+        self.buffer.extend_from_slice(value);
+        self
+    }
+
+    pub(crate) fn string(
+        &mut self,
+        field: u32,
+        value: &str,
+    ) -> &mut Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    pub(crate) fn message(
+        &mut self,
+        field: u32,
+        build: impl FnOnce(&mut Message),
+    ) -> &mut Self {
+        let mut message = Message::new();
+        build(&mut message);
+        self.bytes(field, &message.buffer)
+    }
+}
+
+// Decodes what Message writes, so the exporter tests can check the field layout
+#[cfg(test)]
+pub(crate) mod reader {
+    use core::convert::TryInto;
+    use std::vec::Vec;
+
+    #[derive(Debug, PartialEq)]
+    pub(crate) enum Field<'a> {
+        Varint(u64),
+        Fixed64(u64),
+        Bytes(&'a [u8]),
+    }
+
+    fn varint(bytes: &mut &[u8]) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let (byte, rest) = bytes.split_first().expect("truncated varint");
+            *bytes = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    fn take<'a>(
+        bytes: &mut &'a [u8],
+        len: usize,
+    ) -> &'a [u8] {
+        assert!(bytes.len() >= len, "truncated field");
+        let (value, rest) = bytes.split_at(len);
+        *bytes = rest;
+        value
+    }
+
+    /// Splits a message into its fields, in the order they were written
+    pub(crate) fn decode(mut bytes: &[u8]) -> Vec<(u32, Field<'_>)> {
+        let mut fields = Vec::new();
+        while !bytes.is_empty() {
+            let key = varint(&mut bytes);
+            let field = match key as u32 & 7 {
+                super::VARINT => Field::Varint(varint(&mut bytes)),
+                super::FIXED64 => {
+                    Field::Fixed64(u64::from_le_bytes(take(&mut bytes, 8).try_into().unwrap()))
+                }
+                super::LENGTH_DELIMITED => {
+                    let len = varint(&mut bytes) as usize;
+                    Field::Bytes(take(&mut bytes, len))
+                }
+                wire_type => panic!("unexpected wire type {}", wire_type),
+            };
+            fields.push(((key >> 3) as u32, field));
+        }
+        fields
+    }
+
+    /// Decodes the message in the only occurrence of the given field
+    pub(crate) fn message<'a>(
+        fields: &[(u32, Field<'a>)],
+        number: u32,
+    ) -> Vec<(u32, Field<'a>)> {
+        let mut found = fields.iter().filter(|(field, _)| *field == number);
+        match (found.next(), found.next()) {
+            (Some((_, Field::Bytes(bytes))), None) => decode(bytes),
+            other => panic!(
+                "expected one message in field {}, found {:?}",
+                number, other
+            ),
+        }
+    }
+
+    /// Decodes a packed repeated varint field
+    pub(crate) fn packed(mut bytes: &[u8]) -> Vec<u64> {
+        let mut values = Vec::new();
+        while !bytes.is_empty() {
+            values.push(varint(&mut bytes));
+        }
+        values
+    }
+}
@@ -0,0 +1,176 @@
+//! Writes captures in [speedscope](https://www.speedscope.app)'s "evented" file format, with one
+//! profile per thread. Speedscope's left heavy and sandwich views aggregate time spent in each
+//! scope across the whole capture.
+//!
+//! ```
+//! profiling::register_thread!("Main Thread");
+//! {
+//!     profiling::scope!("Update");
+//! }
+//!
+//! let mut json = Vec::new();
+//! profiling::builtin::speedscope::write(&profiling::builtin::drain(), &mut json).unwrap();
+//! ```
+
+use super::json;
+use super::{Capture, EventKind};
+use crate::ScopeLocation;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::vec::Vec;
+
+/// Writes every thread as an evented profile where scopes open and close frames. Frames are
+/// shared between threads and identified by scope name and location. Markers become frames that
+/// open and close at the same time. Frame ends and scope `data` have no equivalent in speedscope
+/// and are not written.
+pub fn write(
+    capture: &Capture,
+    writer: impl Write,
+) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+
+    // Frames are written after the profiles that refer to them, so collect them as we go
+    let mut frames = Vec::new();
+    let mut frame_indices = HashMap::new();
+    let mut frame_index = |name, location: &ScopeLocation| {
+        let key = (name, location.file, location.line);
+        *frame_indices.entry(key).or_insert_with(|| {
+            frames.push(key);
+            frames.len() - 1
+        })
+    };
+
+    writer.write_all(b"{\"$schema\":\"https://www.speedscope.app/file-format-schema.json\",\"exporter\":\"profiling\",\"profiles\":[")?;
+    let mut separator = "";
+    for thread in &capture.threads {
+        let start_ns = thread
+            .events
+            .first()
+            .map(|event| event.time_ns)
+            .unwrap_or(0);
+        let end_ns = thread.events.last().map(|event| event.time_ns).unwrap_or(0);
+
+        write!(writer, "{}\n{{\"type\":\"evented\",\"name\":", separator)?;
+        json::write_str(&mut writer, &thread.name)?;
+        write!(
+            writer,
+            ",\"unit\":\"nanoseconds\",\"startValue\":{},\"endValue\":{},\"events\":[",
+            start_ns, end_ns
+        )?;
+        separator = ",";
+
+        // Ends without a begin come from scopes that were open when an earlier capture was drained
+        let mut open = Vec::new();
+        let mut event_separator = "";
+        for event in &thread.events {
+            let (kind, frame) = match &event.kind {
+                EventKind::ScopeBegin { name, location, .. } => {
+                    let frame = frame_index(name, location);
+                    open.push(frame);
+                    ("O", frame)
+                }
+                // Speedscope has no instant events, so markers are zero-length frames
+                EventKind::Marker { name, location, .. } => {
+                    let frame = frame_index(name, location);
+                    write!(
+                        writer,
+                        "{}{{\"type\":\"O\",\"frame\":{},\"at\":{}}}",
+                        event_separator, frame, event.time_ns
+                    )?;
+                    event_separator = ",";
+                    ("C", frame)
+                }
+                EventKind::ScopeEnd => match open.pop() {
+                    Some(frame) => ("C", frame),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            write!(
+                writer,
+                "{}{{\"type\":\"{}\",\"frame\":{},\"at\":{}}}",
+                event_separator, kind, frame, event.time_ns
+            )?;
+            event_separator = ",";
+        }
+
+        // Close scopes that were still open when the capture was taken
+        while let Some(frame) = open.pop() {
+            write!(
+                writer,
+                "{}{{\"type\":\"C\",\"frame\":{},\"at\":{}}}",
+                event_separator, frame, end_ns
+            )?;
+            event_separator = ",";
+        }
+
+        writer.write_all(b"]}")?;
+    }
+
+    writer.write_all(b"\n],\"shared\":{\"frames\":[")?;
+    let mut separator = "";
+    for (name, file, line) in frames {
+        write!(writer, "{}\n{{\"name\":", separator)?;
+        json::write_str(&mut writer, name)?;
+        writer.write_all(b",\"file\":")?;
+        json::write_str(&mut writer, file)?;
+        write!(writer, ",\"line\":{}}}", line)?;
+        separator = ",";
+    }
+
+    writer.write_all(b"\n]}}\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builtin::test_capture;
+    use serde_json::{json, Value};
+    use std::vec::Vec;
+
+    #[test]
+    fn frames_and_nested_events() {
+        let mut output = Vec::new();
+        super::write(&test_capture(), &mut output).unwrap();
+        let file: Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(
+            file["shared"]["frames"],
+            json!([
+                { "name": "outer", "file": "src/app.rs", "line": 10 },
+                { "name": "inner", "file": "src/app.rs", "line": 10 }
+            ])
+        );
+
+        let profiles = file["profiles"].as_array().unwrap();
+        assert_eq!(profiles.len(), 1);
+        let profile = &profiles[0];
+        assert_eq!(profile["type"], "evented");
+        assert_eq!(profile["name"], "Main Thread");
+        assert_eq!(profile["unit"], "nanoseconds");
+        assert_eq!(profile["startValue"], 1000);
+        assert_eq!(profile["endValue"], 9000);
+        assert_eq!(
+            profile["events"],
+            json!([
+                { "type": "O", "frame": 0, "at": 1000 },
+                { "type": "O", "frame": 1, "at": 2000 },
+                { "type": "C", "frame": 1, "at": 5000 },
+                { "type": "C", "frame": 0, "at": 9000 }
+            ])
+        );
+
+        // Speedscope rejects a profile whose closes don't match the innermost open frame
+        let mut open = Vec::new();
+        for event in profile["events"].as_array().unwrap() {
+            let frame = event["frame"].as_u64().unwrap();
+            match event["type"].as_str().unwrap() {
+                "O" => open.push(frame),
+                "C" => assert_eq!(open.pop(), Some(frame)),
+                other => panic!("unexpected event type {}", other),
+            }
+        }
+        assert!(open.is_empty());
+    }
+}
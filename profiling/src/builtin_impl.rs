@@ -0,0 +1,330 @@
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builtin_scope {
+    (name = $fmt:literal; $name:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new_formatted(
+                $name,
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    // Note: literal patterns provided as an optimization since they can skip an allocation.
+    ($name:literal, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new(
+                $name,
+                None,
+                Some($color),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:literal, $data:expr, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new(
+                $name,
+                Some($data),
+                Some($color),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new_dynamic(
+                $name,
+                None,
+                Some($color),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $data:expr, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new_dynamic(
+                $name,
+                Some($data),
+                Some($color),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:literal, $($key:ident = $value:expr),+ $(,)?) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new_with_fields(
+                $name,
+                &$crate::__scope_fields!($($key = $value),+),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $($key:ident = $value:expr),+ $(,)?) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new_dynamic_with_fields(
+                $name,
+                &$crate::__scope_fields!($($key = $value),+),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:literal, $fmt:literal, $($arg:tt)+) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new_with_args(
+                $name,
+                format_args!($fmt, $($arg)+),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $fmt:literal, $($arg:tt)+) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new_dynamic_with_args(
+                $name,
+                format_args!($fmt, $($arg)+),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new(
+                $name,
+                None,
+                None,
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:literal, $data:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new(
+                $name,
+                Some($data),
+                None,
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new_dynamic(
+                $name,
+                None,
+                None,
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $data:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            Some($crate::builtin::BuiltinScope::new_dynamic(
+                $name,
+                Some($data),
+                None,
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builtin_function_scope {
+    (color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            let _location = $crate::__scope_location!();
+            Some($crate::builtin::BuiltinScope::new(
+                _location.function_name,
+                None,
+                Some($color),
+                _location,
+            ))
+        } else {
+            None
+        }
+    };
+    ($data:expr, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            let _location = $crate::__scope_location!();
+            Some($crate::builtin::BuiltinScope::new(
+                _location.function_name,
+                Some($data),
+                Some($color),
+                _location,
+            ))
+        } else {
+            None
+        }
+    };
+    () => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            let _location = $crate::__scope_location!();
+            Some($crate::builtin::BuiltinScope::new(
+                _location.function_name,
+                None,
+                None,
+                _location,
+            ))
+        } else {
+            None
+        }
+    };
+    ($data:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            let _location = $crate::__scope_location!();
+            Some($crate::builtin::BuiltinScope::new(
+                _location.function_name,
+                Some($data),
+                None,
+                _location,
+            ))
+        } else {
+            None
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builtin_register_thread {
+    () => {
+        // The builtin backend already falls back to the thread's name
+    };
+    ($name:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::set_thread_name($name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builtin_finish_frame {
+    () => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::finish_frame();
+        }
+    };
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::finish_named_frame($name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builtin_frame_start {
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::frame_start($name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builtin_frame_end {
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::frame_end($name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builtin_plot {
+    ($name:literal, $value:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::plot($name, $value as f64);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builtin_message {
+    ($text:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::message($text, None);
+        }
+    };
+    ($text:expr, $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::message($text, Some($color));
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __builtin_marker {
+    ($scope:ident; $name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::marker(
+                $name,
+                None,
+                $crate::MarkerScope::$scope,
+                $crate::__scope_location!(),
+            );
+        }
+    };
+    ($scope:ident; $name:literal, $data:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::marker(
+                $name,
+                Some($data),
+                $crate::MarkerScope::$scope,
+                $crate::__scope_location!(),
+            );
+        }
+    };
+    ($scope:ident; $name:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::marker_dynamic(
+                $name,
+                None,
+                $crate::MarkerScope::$scope,
+                $crate::__scope_location!(),
+            );
+        }
+    };
+    ($scope:ident; $name:expr, $data:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Builtin) {
+            $crate::builtin::marker_dynamic(
+                $name,
+                Some($data),
+                $crate::MarkerScope::$scope,
+                $crate::__scope_location!(),
+            );
+        }
+    };
+}
@@ -0,0 +1,535 @@
+use crate::{FieldValue, MarkerScope, ScopeLocation};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A user-provided backend for `profile-with-custom`. Register it with [`set_custom_backend`] and
+/// the macros will call into it like any of the built-in backends.
+///
+/// ```
+/// struct MyTelemetry;
+///
+/// impl profiling::ProfilerBackend for MyTelemetry {
+///     fn begin_scope(
+///         &self,
+///         name: &str,
+///         data: Option<&str>,
+///         location: &profiling::ScopeLocation,
+///     ) {
+///         // forward to an in-house telemetry system
+///     }
+///
+///     fn end_scope(&self) {}
+///
+///     fn register_thread(
+///         &self,
+///         name: &str,
+///     ) {
+///     }
+///
+///     fn finish_frame(&self) {}
+/// }
+///
+/// profiling::set_custom_backend(&MyTelemetry).unwrap();
+/// ```
+pub trait ProfilerBackend: Send + Sync {
+    /// Called when a scope opens. Scopes on a thread are strictly nested, so each call is paired
+    /// with a later call to `end_scope` on the same thread.
+    fn begin_scope(
+        &self,
+        name: &str,
+        data: Option<&str>,
+        location: &ScopeLocation,
+    );
+
+    /// Called instead of `begin_scope` when a scope has a color in the form 0xRRGGBB. By default
+    /// this ignores the color and calls `begin_scope`.
+    fn begin_colored_scope(
+        &self,
+        name: &str,
+        data: Option<&str>,
+        _color: u32,
+        location: &ScopeLocation,
+    ) {
+        self.begin_scope(name, data, location);
+    }
+
+    /// Called instead of `begin_scope` when a scope has fields, i.e. `scope!("load", bytes = n)`.
+    /// By default this ignores the fields and calls `begin_scope` without data.
+    fn begin_scope_with_fields(
+        &self,
+        name: &str,
+        _fields: &[(&'static str, FieldValue<'_>)],
+        location: &ScopeLocation,
+    ) {
+        self.begin_scope(name, None, location);
+    }
+
+    /// Called when the most recently opened scope on the current thread closes
+    fn end_scope(&self);
+
+    /// Called by `register_thread!` with the name of the current thread
+    fn register_thread(
+        &self,
+        name: &str,
+    );
+
+    /// Called by `finish_frame!`
+    fn finish_frame(&self);
+
+    /// Called by `finish_frame!(name)` to end a frame of a frame set other than the main one. Does
+    /// nothing by default.
+    fn finish_named_frame(
+        &self,
+        _name: &'static str,
+    ) {
+    }
+
+    /// Called by `frame_start!` when a discontinuous frame begins. Does nothing by default.
+    fn frame_start(
+        &self,
+        _name: &'static str,
+    ) {
+    }
+
+    /// Called by `frame_end!` when a discontinuous frame ends. Does nothing by default.
+    fn frame_end(
+        &self,
+        _name: &'static str,
+    ) {
+    }
+
+    /// Called by `plot!` with the name of the plot and its new value. Does nothing by default.
+    fn plot(
+        &self,
+        _name: &'static str,
+        _value: f64,
+    ) {
+    }
+
+    /// Called by `message!` with the text of the message and its color in the form 0xRRGGBB, if
+    /// one was given. Does nothing by default.
+    fn message(
+        &self,
+        _text: &str,
+        _color: Option<u32>,
+    ) {
+    }
+
+    /// Called by `marker!`. By default this records a zero-length scope on the current thread.
+    fn marker(
+        &self,
+        name: &str,
+        data: Option<&str>,
+        _scope: MarkerScope,
+        location: &ScopeLocation,
+    ) {
+        self.begin_scope(name, data, location);
+        self.end_scope();
+    }
+
+    /// Called when a task is created, i.e. by `profiling::tokio::spawn`. The id is unique for the
+    /// lifetime of the process, and `task_end` is called with it later, possibly on another thread.
+    /// Does nothing by default.
+    fn task_begin(
+        &self,
+        _id: u64,
+        _name: &'static str,
+    ) {
+    }
+
+    /// Called when the task with this id completes or is dropped. Does nothing by default.
+    fn task_end(
+        &self,
+        _id: u64,
+    ) {
+    }
+
+    /// Called when work is handed off from the current scope to other threads, i.e. to the jobs of
+    /// a rayon iterator or a thread spawned with `profiling::thread`. Does nothing by default.
+    fn flow_begin(
+        &self,
+        _id: u64,
+    ) {
+    }
+
+    /// Called on the thread that runs work handed off by the `flow_begin` with this id, either
+    /// inside the first scope of the work or, when no scope is open, just before it. There can be
+    /// several calls for the same id, one for each thread the work ran on. Does nothing by
+    /// default.
+    fn flow_end(
+        &self,
+        _id: u64,
+    ) {
+    }
+}
+
+/// Returned by [`set_custom_backend`] if a backend was already registered
+#[derive(Debug)]
+pub struct SetCustomBackendError(());
+
+impl core::fmt::Display for SetCustomBackendError {
+    fn fmt(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+    ) -> core::fmt::Result {
+        f.write_str("a custom profiler backend has already been registered")
+    }
+}
+
+const UNINITIALIZED: usize = 0;
+const INITIALIZING: usize = 1;
+const INITIALIZED: usize = 2;
+
+static STATE: AtomicUsize = AtomicUsize::new(UNINITIALIZED);
+static mut BACKEND: Option<&'static dyn ProfilerBackend> = None;
+
+/// Registers the backend used by `profile-with-custom`. This can only be done once, and until it is
+/// done the macros do nothing for this backend.
+pub fn set_custom_backend(
+    backend: &'static dyn ProfilerBackend
+) -> Result<(), SetCustomBackendError> {
+    match STATE.compare_exchange(
+        UNINITIALIZED,
+        INITIALIZING,
+        Ordering::Acquire,
+        Ordering::Relaxed,
+    ) {
+        Ok(_) => {
+            // SAFETY: STATE ensures this is only written once, before any reads
+            unsafe {
+                BACKEND = Some(backend);
+            }
+            STATE.store(INITIALIZED, Ordering::Release);
+            Ok(())
+        }
+        Err(_) => Err(SetCustomBackendError(())),
+    }
+}
+
+/// Returns the backend registered with [`set_custom_backend`], if any
+#[inline]
+pub fn custom_backend() -> Option<&'static dyn ProfilerBackend> {
+    if STATE.load(Ordering::Acquire) == INITIALIZED {
+        // SAFETY: BACKEND is never written again once STATE is INITIALIZED
+        unsafe { BACKEND }
+    } else {
+        None
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __custom_scope {
+    (name = $fmt:literal; $name:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                &format!("{}", $name),
+                None,
+                None,
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, color = $color:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                $name,
+                None,
+                Some($color),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $data:expr, color = $color:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                $name,
+                Some($data),
+                Some($color),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $($key:ident = $value:expr),+ $(,)?) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            Some($crate::custom::CustomScope::new_with_fields(
+                _backend,
+                $name,
+                &$crate::__scope_fields!($($key = $value),+),
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $fmt:literal, $($arg:tt)+) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                $name,
+                Some(&format!($fmt, $($arg)+)),
+                None,
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                $name,
+                None,
+                None,
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $data:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                $name,
+                Some($data),
+                None,
+                $crate::__scope_location!(),
+            ))
+        } else {
+            None
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __custom_function_scope {
+    (color = $color:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            let _location = $crate::__scope_location!();
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                _location.function_name,
+                None,
+                Some($color),
+                _location,
+            ))
+        } else {
+            None
+        }
+    };
+    ($data:expr, color = $color:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            let _location = $crate::__scope_location!();
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                _location.function_name,
+                Some($data),
+                Some($color),
+                _location,
+            ))
+        } else {
+            None
+        }
+    };
+    () => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            let _location = $crate::__scope_location!();
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                _location.function_name,
+                None,
+                None,
+                _location,
+            ))
+        } else {
+            None
+        }
+    };
+    ($data:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            let _location = $crate::__scope_location!();
+            Some($crate::custom::CustomScope::new(
+                _backend,
+                _location.function_name,
+                Some($data),
+                None,
+                _location,
+            ))
+        } else {
+            None
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __custom_register_thread {
+    () => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            let thread_name = std::thread::current()
+                .name()
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| format!("Thread {:?}", std::thread::current().id()));
+
+            _backend.register_thread(&thread_name);
+        }
+    };
+    ($name:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.register_thread($name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __custom_finish_frame {
+    () => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.finish_frame();
+        }
+    };
+    ($name:literal) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.finish_named_frame($name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __custom_frame_start {
+    ($name:literal) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.frame_start($name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __custom_frame_end {
+    ($name:literal) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.frame_end($name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __custom_plot {
+    ($name:literal, $value:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.plot($name, $value as f64);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __custom_message {
+    ($text:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.message($text, None);
+        }
+    };
+    ($text:expr, $color:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.message($text, Some($color));
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __custom_marker {
+    ($scope:ident; $name:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.marker(
+                $name,
+                None,
+                $crate::MarkerScope::$scope,
+                &$crate::__scope_location!(),
+            );
+        }
+    };
+    ($scope:ident; $name:expr, $data:expr) => {
+        if let Some(_backend) = $crate::custom::enabled_backend() {
+            _backend.marker(
+                $name,
+                Some($data),
+                $crate::MarkerScope::$scope,
+                &$crate::__scope_location!(),
+            );
+        }
+    };
+}
+
+//
+// RAII wrapper to support the custom backend. This is public as it needs to be callable from
+// macros but is not intended for direct use.
+//
+#[doc(hidden)]
+pub mod custom {
+    use super::{custom_backend, ProfilerBackend};
+    use crate::{Backend, FieldValue, ScopeLocation};
+
+    #[inline(always)]
+    pub fn enabled_backend() -> Option<&'static dyn ProfilerBackend> {
+        if crate::is_backend_enabled(Backend::Custom) {
+            custom_backend()
+        } else {
+            None
+        }
+    }
+
+    pub struct CustomScope {
+        backend: &'static dyn ProfilerBackend,
+    }
+
+    impl CustomScope {
+        #[inline]
+        pub fn new(
+            backend: &'static dyn ProfilerBackend,
+            name: &str,
+            data: Option<&str>,
+            color: Option<u32>,
+            location: ScopeLocation,
+        ) -> Self {
+            match color {
+                Some(color) => backend.begin_colored_scope(name, data, color, &location),
+                None => backend.begin_scope(name, data, &location),
+            }
+            CustomScope { backend }
+        }
+
+        #[inline]
+        pub fn new_with_fields(
+            backend: &'static dyn ProfilerBackend,
+            name: &str,
+            fields: &[(&'static str, FieldValue<'_>)],
+            location: ScopeLocation,
+        ) -> Self {
+            backend.begin_scope_with_fields(name, fields, &location);
+            CustomScope { backend }
+        }
+    }
+
+    impl Drop for CustomScope {
+        fn drop(&mut self) {
+            self.backend.end_scope();
+        }
+    }
+}
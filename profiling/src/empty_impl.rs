@@ -4,22 +4,63 @@
 ///    datafield. Details of this depend on the API, but it should be a &str. If the extra data is
 ///    named, it will be named "tag". Some APIs support adding more data (for example, `optic::tag!`)
 ///
+/// Either variant can end with `color = 0xRRGGBB` to color the scope in backends that support it.
+///
+/// Instead of the data, a scope can have typed fields: `scope!(name, key = value, ...)`. Values
+/// can be integers, floats, bools or strings (see [`AsFieldValue`](crate::AsFieldValue)).
+/// Backends with typed fields record them as such, the others record them as text.
+///
+/// The data can also be given as a format string and arguments: `scope!(name, "iteration {}", i)`.
+/// It is only formatted when a backend records it, so this is cheaper than passing `format!(...)`.
+/// At least one argument is needed, otherwise the string is used as plain data.
+/// Likewise, `scope!(name = "worker {}", id)` formats the name of the scope. Backends that need
+/// static names use the format string as the name and record the formatted name as data.
+///
 /// ```
 /// profiling::scope!("outer");
-/// for _ in 0..10 {
-///     profiling::scope!("inner", format!("iteration {}").as_str());
+/// for i in 0..10 {
+///     profiling::scope!("inner", "iteration {}", i);
 /// }
+/// profiling::scope!("physics", color = 0x2E7D32);
+///
+/// let path = "level1.dat";
+/// profiling::scope!("load", path = path, bytes = 4096, cached = true);
+///
+/// let id = 3;
+/// profiling::scope!(name = "worker {}", id);
 /// ```
 #[macro_export]
 macro_rules! scope {
+    (name = $fmt:literal, $($arg:tt)+) => {};
+    ($name:expr, $fmt:literal, $($arg:tt)+) => {};
+    ($name:expr, color = $color:expr) => {};
+    ($name:expr, $data:expr, color = $color:expr) => {};
+    ($name:expr, $($key:ident = $value:expr),+ $(,)?) => {};
     ($name:expr) => {};
     ($name:expr, $data:expr) => {};
 }
 
+/// Opens a scope like `scope!` and returns a [`ScopeGuard`](crate::ScopeGuard) that ends it when
+/// dropped, instead of at the end of the enclosing block. Accepts the same arguments as `scope!`.
+///
+/// ```
+/// let scope = profiling::scope_guard!("load");
+/// // ...
+/// drop(scope);
+/// ```
+#[macro_export]
+macro_rules! scope_guard {
+    ($($args:tt)*) => {
+        $crate::ScopeGuard::new(None)
+    };
+}
+
 /// Opens a scope automatically named after the current function.
 /// - profiling::function_scope!() - Opens a scope with the current function name
 /// - profiling::function_scope!(data: &str) - Opens a scope with the current function name and an extra data field.
 ///
+/// Like `scope!`, either variant can end with `color = 0xRRGGBB`.
+///
 /// ```
 /// fn function_a(){
 ///     profiling::function_scope!();
@@ -27,9 +68,14 @@ macro_rules! scope {
 /// fn function_b(iteration: u32){
 ///     profiling::function_scope!(format!("iteration {}", iteration).as_str());
 /// }
+/// fn function_c(){
+///     profiling::function_scope!(color = 0x1565C0);
+/// }
 /// ```
 #[macro_export]
 macro_rules! function_scope {
+    (color = $color:expr) => {};
+    ($data:expr, color = $color:expr) => {};
     () => {};
     ($data:expr) => {};
 }
@@ -45,8 +91,86 @@ macro_rules! register_thread {
 }
 
 /// Finishes the frame. This isn't strictly necessary for some kinds of applications but a pretty
-/// normal thing to track in games.
+/// normal thing to track in games. Two variants:
+///  - finish_frame!() - Finishes the main frame
+///  - finish_frame!(name: &str) - Finishes a frame of a separate frame set, i.e. a physics tick
+///    that runs at a different rate than rendering. The name must be a string literal.
+///
+/// ```
+/// profiling::finish_frame!();
+/// profiling::finish_frame!("Physics");
+/// ```
 #[macro_export]
 macro_rules! finish_frame {
     () => {};
+    ($name:literal) => {};
+}
+
+/// Starts a discontinuous frame, which is ended by `frame_end!` with the same name. Unlike
+/// `finish_frame!`, there may be gaps between these frames. The name must be a string literal.
+///
+/// ```
+/// profiling::frame_start!("Loading");
+/// // load things...
+/// profiling::frame_end!("Loading");
+/// ```
+#[macro_export]
+macro_rules! frame_start {
+    ($name:literal) => {};
+}
+
+/// Ends a discontinuous frame started by `frame_start!` with the same name
+#[macro_export]
+macro_rules! frame_end {
+    ($name:literal) => {};
+}
+
+/// Records a value at the current time in a plot (sometimes called a counter), which most
+/// profilers graph over time alongside the scopes. The name must be a string literal. The value
+/// can be any numeric type and is converted to an f64.
+///
+/// ```
+/// let entities = vec![0; 100];
+/// profiling::plot!("Entities", entities.len());
+/// ```
+#[macro_export]
+macro_rules! plot {
+    ($name:literal, $value:expr) => {};
+}
+
+/// Records a free-form text message at the current time on the current thread, which shows up on
+/// the timeline. Two variants:
+///  - profiling::message!(text: &str) - Records the message
+///  - profiling::message!(text: &str, color: u32) - Records the message with a color in the form
+///    0xRRGGBB, for backends that support it
+///
+/// ```
+/// profiling::message!("Level loaded");
+/// profiling::message!("GC triggered", 0xFF0000);
+/// ```
+#[macro_export]
+macro_rules! message {
+    ($text:expr) => {};
+    ($text:expr, $color:expr) => {};
+}
+
+/// Records a point in time without a duration. Backends without instant events record a
+/// zero-length scope instead. Four variants:
+///  - profiling::marker!(name: &str) - Records a marker on the current thread
+///  - profiling::marker!(name: &str, data: &str) - Records a marker with an extra data field, like
+///    `scope!`
+///  - profiling::marker!(name: &str, scope = Process) - Records a marker that applies to the
+///    given [`MarkerScope`](crate::MarkerScope): `Thread`, `Process` or `Global`
+///  - profiling::marker!(name: &str, data: &str, scope = Global)
+///
+/// ```
+/// profiling::marker!("Checkpoint");
+/// profiling::marker!("Level loaded", "level1", scope = Process);
+/// ```
+#[macro_export]
+macro_rules! marker {
+    ($name:expr) => {};
+    ($name:expr, scope = $scope:ident) => {};
+    ($name:expr, $data:expr) => {};
+    ($name:expr, $data:expr, scope = $scope:ident) => {};
 }
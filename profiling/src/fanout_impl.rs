@@ -0,0 +1,857 @@
+//
+// Used when at least one backend is enabled. Each backend module exports hidden macros prefixed with
+// its name, and the public macros here forward their arguments to every one of them. Backends that
+// are not compiled in get stubs at the bottom of this file that expand to nothing.
+//
+// Expressions in the arguments are bound to variables before they are passed on, so they are
+// evaluated once no matter how many backends are enabled. Names are passed on as the original tokens
+// since tracing needs a constant and some backends have cheaper arms for literals. Each backend also
+// checks whether it is selected at runtime (see set_backend), and scopes check
+// is_any_backend_enabled first so that they cost a single branch when profiling is turned off.
+//
+// A trailing `color = ...` would also parse as an assignment expression, so the backends match the
+// color variants of scope!/function_scope! before the data variants.
+//
+
+/// Opens a scope in every enabled backend. See the crate-level docs for the accepted arguments.
+#[macro_export]
+macro_rules! scope {
+    ($($args:tt)*) => {
+        let _profiling_scope = $crate::scope_guard!($($args)*);
+    };
+}
+
+/// Opens a scope in every enabled backend and returns a [`ScopeGuard`](crate::ScopeGuard) that
+/// ends it when dropped.
+#[macro_export]
+macro_rules! scope_guard {
+    ($($args:tt)*) => {
+        $crate::ScopeGuard::new(if $crate::is_any_backend_enabled() {
+            Some($crate::__scope_args!($($args)*))
+        } else {
+            None
+        })
+    };
+}
+
+// A formatted name is bound as fmt::Arguments, so the backends still only format it when they
+// record the scope. The format string is passed along for the backends that need a static name.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __scope_args {
+    (name = $fmt:literal, $($arg:tt)+) => {
+        match (format_args!($fmt, $($arg)+),) {
+            (_name,) => $crate::__backend_scopes!(name = $fmt; _name),
+        }
+    };
+    ($($args:tt)*) => {
+        $crate::__scope_name!([] $($args)*)
+    };
+}
+
+// Moves tokens into the brackets until the comma after the name
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __scope_name {
+    ([$($name:tt)+] , $($rest:tt)*) => {
+        $crate::__scope_rest!([$($name)+] $($rest)*)
+    };
+    ([$($name:tt)+]) => {
+        $crate::__backend_scopes!($($name)+)
+    };
+    ([$($name:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__scope_name!([$($name)* $next] $($rest)*)
+    };
+}
+
+// Matches the arguments after the name in the same order as the backends do
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __scope_rest {
+    ([$($name:tt)+] color = $color:expr) => {
+        match ($color,) {
+            (_color,) => $crate::__backend_scopes!($($name)+, color = _color),
+        }
+    };
+    ([$($name:tt)+] $data:expr, color = $color:expr) => {
+        match ($data, $color) {
+            (_data, _color) => $crate::__backend_scopes!($($name)+, _data, color = _color),
+        }
+    };
+    ([$($name:tt)+] $($key:ident = $value:expr),+ $(,)?) => {
+        $crate::__scope_fields_args!([$($name)+] [] $($key = $value),+)
+    };
+    // A formatted tag is bound as fmt::Arguments, like a formatted name
+    ([$($name:tt)+] $fmt:literal, $($arg:tt)+) => {
+        match (format_args!($fmt, $($arg)+),) {
+            (_data,) => $crate::__backend_scopes!($($name)+, "{}", _data),
+        }
+    };
+    ([$($name:tt)+] $data:expr) => {
+        match ($data,) {
+            (_data,) => $crate::__backend_scopes!($($name)+, _data),
+        }
+    };
+}
+
+// Binds one field value per expansion, so that hygiene keeps the variables apart and none of them
+// can shadow something the name refers to. Values are borrowed, like the backends do.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __scope_fields_args {
+    ([$($name:tt)+] [$($bound:tt)*]) => {
+        $crate::__backend_scopes!($($name)+, $($bound)*)
+    };
+    ([$($name:tt)+] [$($bound:tt)*] $key:ident = $value:expr $(, $($rest:tt)*)?) => {
+        match (&$value,) {
+            (_value,) => $crate::__scope_fields_args!(
+                [$($name)+] [$($bound)* $key = _value,] $($($rest)*)?
+            ),
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __backend_scopes {
+    ($($args:tt)*) => {
+        (
+            $crate::__puffin_scope!($($args)*),
+            $crate::__optick_scope!($($args)*),
+            $crate::__superluminal_scope!($($args)*),
+            $crate::__tracing_scope!($($args)*),
+            $crate::__tracy_scope!($($args)*),
+            $crate::__custom_scope!($($args)*),
+            $crate::__builtin_scope!($($args)*),
+            $crate::__type_check_scope!($($args)*),
+        )
+    };
+}
+
+/// Opens a scope named after the current function in every enabled backend.
+#[macro_export]
+macro_rules! function_scope {
+    ($($args:tt)*) => {
+        let _profiling_scope = if $crate::is_any_backend_enabled() {
+            Some($crate::__function_scope_args!($($args)*))
+        } else {
+            None
+        };
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __function_scope_args {
+    (color = $color:expr) => {
+        match ($color,) {
+            (_color,) => $crate::__backend_function_scopes!(color = _color),
+        }
+    };
+    ($data:expr, color = $color:expr) => {
+        match ($data, $color) {
+            (_data, _color) => $crate::__backend_function_scopes!(_data, color = _color),
+        }
+    };
+    () => {
+        $crate::__backend_function_scopes!()
+    };
+    ($data:expr) => {
+        match ($data,) {
+            (_data,) => $crate::__backend_function_scopes!(_data),
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __backend_function_scopes {
+    ($($args:tt)*) => {
+        (
+            $crate::__puffin_function_scope!($($args)*),
+            $crate::__optick_function_scope!($($args)*),
+            $crate::__superluminal_function_scope!($($args)*),
+            $crate::__tracing_function_scope!($($args)*),
+            $crate::__tracy_function_scope!($($args)*),
+            $crate::__custom_function_scope!($($args)*),
+            $crate::__builtin_function_scope!($($args)*),
+            $crate::__type_check_function_scope!($($args)*),
+        )
+    };
+}
+
+/// Registers the current thread with every enabled backend.
+#[macro_export]
+macro_rules! register_thread {
+    () => {
+        $crate::__puffin_register_thread!();
+        $crate::__optick_register_thread!();
+        $crate::__superluminal_register_thread!();
+        $crate::__tracing_register_thread!();
+        $crate::__tracy_register_thread!();
+        $crate::__custom_register_thread!();
+        $crate::__builtin_register_thread!();
+        $crate::__type_check_register_thread!();
+    };
+    ($name:expr) => {
+        match ($name,) {
+            (_name,) => {
+                $crate::__puffin_register_thread!(_name);
+                $crate::__optick_register_thread!(_name);
+                $crate::__superluminal_register_thread!(_name);
+                $crate::__tracing_register_thread!(_name);
+                $crate::__tracy_register_thread!(_name);
+                $crate::__custom_register_thread!(_name);
+                $crate::__builtin_register_thread!(_name);
+                $crate::__type_check_register_thread!(_name);
+            }
+        }
+    };
+}
+
+/// Finishes the frame, or a frame of the named frame set, in every enabled backend.
+#[macro_export]
+macro_rules! finish_frame {
+    ($($args:tt)*) => {
+        $crate::__puffin_finish_frame!($($args)*);
+        $crate::__optick_finish_frame!($($args)*);
+        $crate::__superluminal_finish_frame!($($args)*);
+        $crate::__tracing_finish_frame!($($args)*);
+        $crate::__tracy_finish_frame!($($args)*);
+        $crate::__custom_finish_frame!($($args)*);
+        $crate::__builtin_finish_frame!($($args)*);
+        $crate::__type_check_finish_frame!($($args)*);
+    };
+}
+
+/// Starts a discontinuous frame in every enabled backend.
+#[macro_export]
+macro_rules! frame_start {
+    ($name:literal) => {
+        $crate::__puffin_frame_start!($name);
+        $crate::__optick_frame_start!($name);
+        $crate::__superluminal_frame_start!($name);
+        $crate::__tracing_frame_start!($name);
+        $crate::__tracy_frame_start!($name);
+        $crate::__custom_frame_start!($name);
+        $crate::__builtin_frame_start!($name);
+        $crate::__type_check_frame_start!($name);
+    };
+}
+
+/// Ends a discontinuous frame in every enabled backend.
+#[macro_export]
+macro_rules! frame_end {
+    ($name:literal) => {
+        $crate::__puffin_frame_end!($name);
+        $crate::__optick_frame_end!($name);
+        $crate::__superluminal_frame_end!($name);
+        $crate::__tracing_frame_end!($name);
+        $crate::__tracy_frame_end!($name);
+        $crate::__custom_frame_end!($name);
+        $crate::__builtin_frame_end!($name);
+        $crate::__type_check_frame_end!($name);
+    };
+}
+
+/// Records a value in a plot in every enabled backend.
+#[macro_export]
+macro_rules! plot {
+    ($name:literal, $value:expr) => {
+        match ($value,) {
+            (_value,) => {
+                $crate::__puffin_plot!($name, _value);
+                $crate::__optick_plot!($name, _value);
+                $crate::__superluminal_plot!($name, _value);
+                $crate::__tracing_plot!($name, _value);
+                $crate::__tracy_plot!($name, _value);
+                $crate::__custom_plot!($name, _value);
+                $crate::__builtin_plot!($name, _value);
+                $crate::__type_check_plot!($name, _value);
+            }
+        }
+    };
+}
+
+/// Records a text message on the current thread in every enabled backend.
+#[macro_export]
+macro_rules! message {
+    ($text:expr) => {
+        match ($text,) {
+            (_text,) => {
+                $crate::__puffin_message!(_text);
+                $crate::__optick_message!(_text);
+                $crate::__superluminal_message!(_text);
+                $crate::__tracing_message!(_text);
+                $crate::__tracy_message!(_text);
+                $crate::__custom_message!(_text);
+                $crate::__builtin_message!(_text);
+                $crate::__type_check_message!(_text);
+            }
+        }
+    };
+    ($text:expr, $color:expr) => {
+        match ($text, $color) {
+            (_text, _color) => {
+                $crate::__puffin_message!(_text, _color);
+                $crate::__optick_message!(_text, _color);
+                $crate::__superluminal_message!(_text, _color);
+                $crate::__tracing_message!(_text, _color);
+                $crate::__tracy_message!(_text, _color);
+                $crate::__custom_message!(_text, _color);
+                $crate::__builtin_message!(_text, _color);
+                $crate::__type_check_message!(_text, _color);
+            }
+        }
+    };
+}
+
+/// Records a point in time in every enabled backend. An optional trailing `scope = Thread`,
+/// `scope = Process` or `scope = Global` selects the [`MarkerScope`](crate::MarkerScope).
+#[macro_export]
+macro_rules! marker {
+    ($($args:tt)*) => {
+        $crate::__marker_args!([] $($args)*);
+    };
+}
+
+// Splits the optional scope off the end of the arguments one token at a time, so that the name
+// still reaches the backends as the original tokens (i.e. a literal name stays a literal)
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __marker_args {
+    ([$($args:tt)*] , scope = $scope:ident) => {
+        $crate::__marker!($scope; [] $($args)*);
+    };
+    ([$($args:tt)*]) => {
+        $crate::__marker!(Thread; [] $($args)*);
+    };
+    ([$($args:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__marker_args!([$($args)* $next] $($rest)*);
+    };
+}
+
+// Then splits the name off the data like __scope_name, and binds the data once
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __marker {
+    ($scope:ident; [$($name:tt)+] , $data:expr) => {
+        match ($data,) {
+            (_data,) => {
+                $crate::__backend_markers!($scope; $($name)+, _data);
+            }
+        }
+    };
+    ($scope:ident; [$($name:tt)+]) => {
+        $crate::__backend_markers!($scope; $($name)+);
+    };
+    ($scope:ident; [$($name:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__marker!($scope; [$($name)* $next] $($rest)*);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __backend_markers {
+    ($($args:tt)*) => {
+        $crate::__puffin_marker!($($args)*);
+        $crate::__optick_marker!($($args)*);
+        $crate::__superluminal_marker!($($args)*);
+        $crate::__tracing_marker!($($args)*);
+        $crate::__tracy_marker!($($args)*);
+        $crate::__custom_marker!($($args)*);
+        $crate::__builtin_marker!($($args)*);
+        $crate::__type_check_marker!($($args)*);
+    };
+}
+
+//
+// Stubs for backends that are not compiled in
+//
+#[cfg(not(feature = "profile-with-puffin"))]
+mod puffin_stubs {
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __puffin_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __puffin_function_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __puffin_register_thread {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __puffin_finish_frame {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __puffin_frame_start {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __puffin_frame_end {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __puffin_plot {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __puffin_message {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __puffin_marker {
+        ($($args:tt)*) => {};
+    }
+}
+
+#[cfg(not(feature = "profile-with-optick"))]
+mod optick_stubs {
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __optick_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __optick_function_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __optick_register_thread {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __optick_finish_frame {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __optick_frame_start {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __optick_frame_end {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __optick_plot {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __optick_message {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __optick_marker {
+        ($($args:tt)*) => {};
+    }
+}
+
+#[cfg(not(feature = "profile-with-superluminal"))]
+mod superluminal_stubs {
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __superluminal_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __superluminal_function_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __superluminal_register_thread {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __superluminal_finish_frame {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __superluminal_frame_start {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __superluminal_frame_end {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __superluminal_plot {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __superluminal_message {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __superluminal_marker {
+        ($($args:tt)*) => {};
+    }
+}
+
+#[cfg(not(feature = "profile-with-tracing"))]
+mod tracing_stubs {
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracing_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracing_function_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracing_register_thread {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracing_finish_frame {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracing_frame_start {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracing_frame_end {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracing_plot {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracing_message {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracing_marker {
+        ($($args:tt)*) => {};
+    }
+}
+
+#[cfg(not(feature = "profile-with-tracy"))]
+mod tracy_stubs {
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracy_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracy_function_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracy_register_thread {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracy_finish_frame {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracy_frame_start {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracy_frame_end {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracy_plot {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracy_message {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __tracy_marker {
+        ($($args:tt)*) => {};
+    }
+}
+
+#[cfg(not(feature = "profile-with-custom"))]
+mod custom_stubs {
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __custom_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __custom_function_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __custom_register_thread {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __custom_finish_frame {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __custom_frame_start {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __custom_frame_end {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __custom_plot {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __custom_message {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __custom_marker {
+        ($($args:tt)*) => {};
+    }
+}
+
+#[cfg(not(feature = "profile-with-builtin"))]
+mod builtin_stubs {
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __builtin_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __builtin_function_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __builtin_register_thread {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __builtin_finish_frame {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __builtin_frame_start {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __builtin_frame_end {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __builtin_plot {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __builtin_message {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __builtin_marker {
+        ($($args:tt)*) => {};
+    }
+}
+
+#[cfg(not(feature = "type-check"))]
+mod type_check_stubs {
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __type_check_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __type_check_function_scope {
+        ($($args:tt)*) => {
+            ()
+        };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __type_check_register_thread {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __type_check_finish_frame {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __type_check_frame_start {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __type_check_frame_end {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __type_check_plot {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __type_check_message {
+        ($($args:tt)*) => {};
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    macro_rules! __type_check_marker {
+        ($($args:tt)*) => {};
+    }
+}
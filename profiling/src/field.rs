@@ -0,0 +1,178 @@
+use core::fmt;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+/// The value of a field passed to `scope!`, i.e. `n` in `scope!("load", bytes = n)`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FieldValue<'a> {
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+    Str(&'a str),
+}
+
+impl fmt::Display for FieldValue<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            FieldValue::I64(value) => value.fmt(f),
+            FieldValue::U64(value) => value.fmt(f),
+            FieldValue::F64(value) => value.fmt(f),
+            FieldValue::Bool(value) => value.fmt(f),
+            FieldValue::Str(value) => value.fmt(f),
+        }
+    }
+}
+
+/// Implemented for the types that can be used as the value of a scope field: integers, floats,
+/// bools and strings. Owned strings need the `std` feature.
+///
+/// ```
+/// let path = String::from("level1.dat");
+/// let kind = std::borrow::Cow::Borrowed("texture");
+/// profiling::scope!("load", path = path, kind = kind, bytes = 4096);
+/// ```
+pub trait AsFieldValue {
+    fn as_field_value(&self) -> FieldValue<'_>;
+}
+
+macro_rules! impl_as_field_value {
+    ($variant:ident($as:ty): $($ty:ty),*) => {
+        $(
+            #[allow(clippy::unnecessary_cast)]
+            impl AsFieldValue for $ty {
+                fn as_field_value(&self) -> FieldValue<'_> {
+                    FieldValue::$variant(*self as $as)
+                }
+            }
+        )*
+    };
+}
+
+impl_as_field_value!(I64(i64): i8, i16, i32, i64, isize);
+impl_as_field_value!(U64(u64): u8, u16, u32, u64, usize);
+impl_as_field_value!(F64(f64): f32, f64);
+impl_as_field_value!(Bool(bool): bool);
+
+impl AsFieldValue for str {
+    fn as_field_value(&self) -> FieldValue<'_> {
+        FieldValue::Str(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl AsFieldValue for std::string::String {
+    fn as_field_value(&self) -> FieldValue<'_> {
+        FieldValue::Str(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl AsFieldValue for std::borrow::Cow<'_, str> {
+    fn as_field_value(&self) -> FieldValue<'_> {
+        FieldValue::Str(self)
+    }
+}
+
+impl<T: AsFieldValue + ?Sized> AsFieldValue for &T {
+    fn as_field_value(&self) -> FieldValue<'_> {
+        (**self).as_field_value()
+    }
+}
+
+// tracing records fields with its own sealed `Value` trait, so each field type names the type it is
+// recorded as. Strings are recorded as `str`, everything else as itself.
+#[cfg(feature = "profile-with-tracing")]
+#[doc(hidden)]
+pub trait AsTracingValue {
+    type Value: tracing::Value + ?Sized;
+
+    fn as_tracing_value(&self) -> &Self::Value;
+}
+
+#[cfg(feature = "profile-with-tracing")]
+macro_rules! impl_as_tracing_value {
+    ($($ty:ty),*) => {
+        $(
+            impl AsTracingValue for $ty {
+                type Value = $ty;
+
+                fn as_tracing_value(&self) -> &$ty {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "profile-with-tracing")]
+impl_as_tracing_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64, bool);
+
+#[cfg(feature = "profile-with-tracing")]
+impl AsTracingValue for str {
+    type Value = str;
+
+    fn as_tracing_value(&self) -> &str {
+        self
+    }
+}
+
+#[cfg(all(feature = "profile-with-tracing", feature = "std"))]
+impl AsTracingValue for std::string::String {
+    type Value = str;
+
+    fn as_tracing_value(&self) -> &str {
+        self
+    }
+}
+
+#[cfg(all(feature = "profile-with-tracing", feature = "std"))]
+impl AsTracingValue for std::borrow::Cow<'_, str> {
+    type Value = str;
+
+    fn as_tracing_value(&self) -> &str {
+        self
+    }
+}
+
+#[cfg(feature = "profile-with-tracing")]
+impl<T: AsTracingValue + ?Sized> AsTracingValue for &T {
+    type Value = T::Value;
+
+    fn as_tracing_value(&self) -> &T::Value {
+        (**self).as_tracing_value()
+    }
+}
+
+/// Formats fields as comma-separated `key = value` pairs. Backends that only take a single data
+/// string for a scope record the fields this way.
+pub struct DisplayFields<'a>(pub &'a [(&'static str, FieldValue<'a>)]);
+
+impl fmt::Display for DisplayFields<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let mut separator = "";
+        for (key, value) in self.0 {
+            write!(f, "{}{} = {}", separator, key, value)?;
+            separator = ", ";
+        }
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __scope_fields {
+    ($($key:ident = $value:expr),+) => {
+        [$((
+            stringify!($key),
+            $crate::AsFieldValue::as_field_value(&$value),
+        )),+]
+    };
+}
@@ -0,0 +1,72 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+// Links the scope that handed off work to the scopes that ran it on other threads. Backends
+// without links between threads (puffin, optick, superluminal, tracy) ignore these.
+#[derive(Clone)]
+pub(crate) struct Flow {
+    #[allow(dead_code)]
+    id: u64,
+    #[cfg(feature = "profile-with-tracing")]
+    span: Option<tracing::Id>,
+}
+
+// Starts at 1 so that 0 is never a valid id
+static NEXT_FLOW_ID: AtomicU64 = AtomicU64::new(1);
+
+impl Flow {
+    // Called inside the scope that hands off the work
+    pub(crate) fn begin() -> Self {
+        let id = NEXT_FLOW_ID.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "profile-with-custom")]
+        if let Some(backend) = crate::custom::enabled_backend() {
+            backend.flow_begin(id);
+        }
+        #[cfg(feature = "profile-with-builtin")]
+        if crate::is_backend_enabled(crate::Backend::Builtin) {
+            crate::builtin::flow_begin(id);
+        }
+
+        Flow {
+            id,
+            #[cfg(feature = "profile-with-tracing")]
+            span: if crate::is_backend_enabled(crate::Backend::Tracing) {
+                tracing::Span::current().id()
+            } else {
+                None
+            },
+        }
+    }
+
+    // Called once on each thread the work runs on, inside its first scope or just before it.
+    // tracing only records the link inside a span, see `thread_span`.
+    pub(crate) fn end(&self) {
+        #[cfg(feature = "profile-with-custom")]
+        if let Some(backend) = crate::custom::enabled_backend() {
+            backend.flow_end(self.id);
+        }
+        #[cfg(feature = "profile-with-builtin")]
+        if crate::is_backend_enabled(crate::Backend::Builtin) {
+            crate::builtin::flow_end(self.id);
+        }
+        #[cfg(feature = "profile-with-tracing")]
+        if let Some(span) = &self.span {
+            tracing::Span::current().follows_from(span.clone());
+        }
+    }
+
+    // Opens a span for a spawned thread to run in, so that tracing has a span on the new thread to
+    // link back to the one that spawned it. Call `end` after this.
+    #[cfg(all(feature = "profile-with-tracing", feature = "std"))]
+    pub(crate) fn thread_span(
+        &self,
+        name: &str,
+    ) -> tracing::span::EnteredSpan {
+        if crate::is_backend_enabled(crate::Backend::Tracing) {
+            tracing::info_span!("thread", name = %name)
+        } else {
+            tracing::Span::none()
+        }
+        .entered()
+    }
+}
@@ -0,0 +1,466 @@
+#[cfg(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-superluminal",
+    feature = "profile-with-tracing",
+    feature = "profile-with-tracy",
+    feature = "profile-with-custom",
+    feature = "profile-with-builtin",
+    feature = "type-check"
+))]
+extern crate std;
+
+use crate::ScopeLocation;
+
+/// An open scope returned by `scope_guard!`. The scope ends when this is dropped, so it can be
+/// ended early with `drop` or stored alongside the work it measures.
+///
+/// ```
+/// struct Request {
+///     _scope: profiling::ScopeGuard,
+/// }
+///
+/// let request = Request {
+///     _scope: profiling::scope_guard!("request"),
+/// };
+/// // ...
+/// drop(request);
+/// ```
+#[must_use = "the scope ends as soon as the guard is dropped"]
+pub struct ScopeGuard {
+    _scopes: Option<BackendScopes>,
+}
+
+impl ScopeGuard {
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn new(scopes: Option<BackendScopes>) -> Self {
+        ScopeGuard { _scopes: scopes }
+    }
+
+    // Opens a scope named by a runtime string, for begin_scope and Instrument
+    pub(crate) fn with_static_name(
+        name: &'static str,
+        location: ScopeLocation,
+    ) -> Self {
+        ScopeGuard::new(if crate::is_any_backend_enabled() {
+            Some((
+                puffin_scope(name, location),
+                optick_scope(name, location),
+                superluminal_scope(name),
+                tracing_scope(name, location),
+                tracy_scope(name, location),
+                custom_scope(name, location),
+                builtin_scope(name, location),
+                (),
+            ))
+        } else {
+            None
+        })
+    }
+}
+
+// What each backend's scope macro returns, in the order the fan-out macros call them. Backends
+// that aren't compiled in return ().
+#[doc(hidden)]
+pub type BackendScopes = (
+    PuffinScope,
+    OptickScope,
+    SuperluminalScope,
+    TracingScope,
+    TracyScope,
+    CustomScope,
+    BuiltinScope,
+    TypeCheckScope,
+);
+
+#[cfg(feature = "profile-with-puffin")]
+type PuffinScope = Option<puffin::ProfilerScope>;
+#[cfg(not(feature = "profile-with-puffin"))]
+type PuffinScope = ();
+
+#[cfg(feature = "profile-with-optick")]
+type OptickScope = Option<optick::OptickCounter>;
+#[cfg(not(feature = "profile-with-optick"))]
+type OptickScope = ();
+
+#[cfg(feature = "profile-with-superluminal")]
+type SuperluminalScope = Option<crate::superluminal::SuperluminalGuard>;
+#[cfg(not(feature = "profile-with-superluminal"))]
+type SuperluminalScope = ();
+
+#[cfg(feature = "profile-with-tracing")]
+type TracingScope = tracing::span::EnteredSpan;
+#[cfg(not(feature = "profile-with-tracing"))]
+type TracingScope = ();
+
+#[cfg(feature = "profile-with-tracy")]
+type TracyScope = Option<tracy_client::Span>;
+#[cfg(not(feature = "profile-with-tracy"))]
+type TracyScope = ();
+
+#[cfg(feature = "profile-with-custom")]
+type CustomScope = Option<crate::custom::CustomScope>;
+#[cfg(not(feature = "profile-with-custom"))]
+type CustomScope = ();
+
+#[cfg(feature = "profile-with-builtin")]
+type BuiltinScope = Option<crate::builtin::BuiltinScope>;
+#[cfg(not(feature = "profile-with-builtin"))]
+type BuiltinScope = ();
+
+type TypeCheckScope = ();
+
+// The scope macros can't be used for names that are only known at runtime because puffin, optick
+// and tracing register each call site once, with the first name they see. These helpers register
+// each name and location they are called with instead, and keep what was registered for next time.
+//
+// Registrations are shared between threads so each name and location is only registered once, but
+// each thread also remembers the ones it has used. After the first scope on a thread, opening the
+// same scope again is a lookup by address that doesn't touch the shared lock.
+
+#[cfg(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-tracing"
+))]
+type Registered<T> = crate::lazy::Lazy<
+    std::sync::Mutex<std::collections::BTreeMap<(&'static str, &'static str, u32), T>>,
+>;
+
+// The address and length of the name, the address of the file and the line
+#[cfg(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-tracing"
+))]
+type Callsite = (usize, usize, usize, u32);
+
+#[cfg(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-tracing"
+))]
+type RegisteredOnThread<T> = core::cell::RefCell<std::collections::BTreeMap<Callsite, T>>;
+
+// The lock isn't held while registering, since that calls into the backend
+#[cfg(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-tracing"
+))]
+fn registered<T: Copy>(
+    on_thread: &'static std::thread::LocalKey<RegisteredOnThread<T>>,
+    shared: &'static Registered<T>,
+    name: &'static str,
+    location: ScopeLocation,
+    register: impl FnOnce() -> T,
+) -> T {
+    let callsite = (
+        name.as_ptr() as usize,
+        name.len(),
+        location.file.as_ptr() as usize,
+        location.line,
+    );
+    // Not available while the thread's locals are being destroyed, which only costs the lock
+    if let Ok(Some(value)) =
+        on_thread.try_with(|registered| registered.borrow().get(&callsite).copied())
+    {
+        return value;
+    }
+
+    let shared = shared.get_or_init(Default::default);
+    let key = (name, location.file, location.line);
+    let lock = || {
+        shared
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    };
+    let found = lock().get(&key).copied();
+    let value = match found {
+        Some(value) => value,
+        None => {
+            let value = register();
+            *lock().entry(key).or_insert(value)
+        }
+    };
+
+    let _ = on_thread.try_with(|registered| registered.borrow_mut().insert(callsite, value));
+    value
+}
+
+#[cfg(feature = "profile-with-puffin")]
+static PUFFIN_SCOPE_IDS: Registered<puffin::ScopeId> = crate::lazy::Lazy::new();
+
+#[cfg(feature = "profile-with-puffin")]
+std::thread_local! {
+    static PUFFIN_SCOPE_IDS_ON_THREAD: RegisteredOnThread<puffin::ScopeId> =
+        core::cell::RefCell::new(std::collections::BTreeMap::new());
+}
+
+#[cfg(feature = "profile-with-puffin")]
+fn puffin_scope(
+    name: &'static str,
+    location: ScopeLocation,
+) -> PuffinScope {
+    if crate::is_backend_enabled(crate::Backend::Puffin) && puffin::are_scopes_on() {
+        let scope_id = registered(
+            &PUFFIN_SCOPE_IDS_ON_THREAD,
+            &PUFFIN_SCOPE_IDS,
+            name,
+            location,
+            || {
+                puffin::ThreadProfiler::call(|profiler| {
+                    profiler.register_named_scope(
+                        name,
+                        location.function_name,
+                        puffin::short_file_name(location.file),
+                        location.line,
+                    )
+                })
+            },
+        );
+        Some(puffin::ProfilerScope::new(scope_id, ""))
+    } else {
+        None
+    }
+}
+#[cfg(not(feature = "profile-with-puffin"))]
+fn puffin_scope(
+    _name: &'static str,
+    _location: ScopeLocation,
+) -> PuffinScope {
+}
+
+#[cfg(feature = "profile-with-optick")]
+static OPTICK_DESCRIPTIONS: Registered<u64> = crate::lazy::Lazy::new();
+
+#[cfg(feature = "profile-with-optick")]
+std::thread_local! {
+    static OPTICK_DESCRIPTIONS_ON_THREAD: RegisteredOnThread<u64> =
+        core::cell::RefCell::new(std::collections::BTreeMap::new());
+}
+
+#[cfg(feature = "profile-with-optick")]
+fn optick_scope(
+    name: &'static str,
+    location: ScopeLocation,
+) -> OptickScope {
+    if crate::is_backend_enabled(crate::Backend::Optick) {
+        let description = registered(
+            &OPTICK_DESCRIPTIONS_ON_THREAD,
+            &OPTICK_DESCRIPTIONS,
+            name,
+            location,
+            || optick::create_description(name, location.file, location.line),
+        );
+        Some(optick::OptickCounter {
+            event_data: optick::push_event(description),
+        })
+    } else {
+        None
+    }
+}
+#[cfg(not(feature = "profile-with-optick"))]
+fn optick_scope(
+    _name: &'static str,
+    _location: ScopeLocation,
+) -> OptickScope {
+}
+
+#[cfg(feature = "profile-with-superluminal")]
+fn superluminal_scope(name: &'static str) -> SuperluminalScope {
+    crate::__superluminal_scope!(name)
+}
+#[cfg(not(feature = "profile-with-superluminal"))]
+fn superluminal_scope(_name: &'static str) -> SuperluminalScope {}
+
+#[cfg(feature = "profile-with-tracing")]
+static TRACING_METADATA: Registered<&'static tracing::Metadata<'static>> = crate::lazy::Lazy::new();
+
+#[cfg(feature = "profile-with-tracing")]
+std::thread_local! {
+    static TRACING_METADATA_ON_THREAD: RegisteredOnThread<&'static tracing::Metadata<'static>> =
+        core::cell::RefCell::new(std::collections::BTreeMap::new());
+}
+
+#[cfg(feature = "profile-with-tracing")]
+fn tracing_scope(
+    name: &'static str,
+    location: ScopeLocation,
+) -> TracingScope {
+    if crate::is_backend_enabled(crate::Backend::Tracing)
+        && tracing::level_enabled!(tracing::Level::INFO)
+    {
+        let metadata = registered(
+            &TRACING_METADATA_ON_THREAD,
+            &TRACING_METADATA,
+            name,
+            location,
+            || NamedCallsite::register(name, location),
+        );
+        if tracing::dispatcher::get_default(|dispatch| dispatch.enabled(metadata)) {
+            let values: [(&tracing::field::Field, Option<&dyn tracing::Value>); 0] = [];
+            tracing::Span::new(metadata, &metadata.fields().value_set(&values))
+        } else {
+            tracing::Span::none()
+        }
+    } else {
+        tracing::Span::none()
+    }
+    .entered()
+}
+#[cfg(not(feature = "profile-with-tracing"))]
+fn tracing_scope(
+    _name: &'static str,
+    _location: ScopeLocation,
+) -> TracingScope {
+}
+
+// What span! declares as statics, created at runtime for a name. Both are leaked, like the statics
+// they stand in for, but only once per name and location.
+#[cfg(feature = "profile-with-tracing")]
+struct NamedCallsite {
+    metadata: core::sync::atomic::AtomicPtr<tracing::Metadata<'static>>,
+}
+
+#[cfg(feature = "profile-with-tracing")]
+impl NamedCallsite {
+    fn register(
+        name: &'static str,
+        location: ScopeLocation,
+    ) -> &'static tracing::Metadata<'static> {
+        use std::boxed::Box;
+
+        // The metadata refers back to the callsite, so the callsite is created first
+        let callsite: &'static NamedCallsite = Box::leak(Box::new(NamedCallsite {
+            metadata: core::sync::atomic::AtomicPtr::new(core::ptr::null_mut()),
+        }));
+        let metadata = Box::into_raw(Box::new(tracing::Metadata::new(
+            name,
+            "profiling",
+            tracing::Level::INFO,
+            Some(location.file),
+            Some(location.line),
+            None,
+            tracing::field::FieldSet::new(&[], tracing::callsite::Identifier(callsite)),
+            tracing::metadata::Kind::SPAN,
+        )));
+        callsite
+            .metadata
+            .store(metadata, core::sync::atomic::Ordering::Release);
+        tracing::callsite::register(callsite);
+        // SAFETY: leaked above, so it is never freed
+        unsafe { &*metadata }
+    }
+}
+
+#[cfg(feature = "profile-with-tracing")]
+impl tracing::Callsite for NamedCallsite {
+    fn set_interest(
+        &self,
+        _interest: tracing::subscriber::Interest,
+    ) {
+        // The subscriber is asked whether each span is enabled instead
+    }
+
+    fn metadata(&self) -> &tracing::Metadata<'_> {
+        // SAFETY: stored before the callsite is registered, which is the first time anything else
+        // can see it
+        unsafe { &*self.metadata.load(core::sync::atomic::Ordering::Acquire) }
+    }
+}
+
+#[cfg(feature = "profile-with-tracy")]
+fn tracy_scope(
+    name: &'static str,
+    location: ScopeLocation,
+) -> TracyScope {
+    if crate::is_backend_enabled(crate::Backend::Tracy) {
+        Some(
+            tracy_client::Client::running()
+                .expect("scope without a running tracy_client::Client")
+                .span_alloc(
+                    Some(name),
+                    location.function_name,
+                    location.file,
+                    location.line,
+                    0,
+                ),
+        )
+    } else {
+        None
+    }
+}
+#[cfg(not(feature = "profile-with-tracy"))]
+fn tracy_scope(
+    _name: &'static str,
+    _location: ScopeLocation,
+) -> TracyScope {
+}
+
+#[cfg(feature = "profile-with-custom")]
+fn custom_scope(
+    name: &'static str,
+    location: ScopeLocation,
+) -> CustomScope {
+    crate::custom::enabled_backend()
+        .map(|backend| crate::custom::CustomScope::new(backend, name, None, None, location))
+}
+#[cfg(not(feature = "profile-with-custom"))]
+fn custom_scope(
+    _name: &'static str,
+    _location: ScopeLocation,
+) -> CustomScope {
+}
+
+#[cfg(feature = "profile-with-builtin")]
+fn builtin_scope(
+    name: &'static str,
+    location: ScopeLocation,
+) -> BuiltinScope {
+    if crate::is_backend_enabled(crate::Backend::Builtin) {
+        Some(crate::builtin::BuiltinScope::new(
+            name, None, None, location,
+        ))
+    } else {
+        None
+    }
+}
+#[cfg(not(feature = "profile-with-builtin"))]
+fn builtin_scope(
+    _name: &'static str,
+    _location: ScopeLocation,
+) -> BuiltinScope {
+}
+
+/// Evaluates a block inside a scope and returns its value. The last argument is the block and the
+/// ones before it are the same as for `scope!`.
+///
+/// ```
+/// fn expensive() -> u32 {
+///     42
+/// }
+///
+/// let value = profiling::scoped!("expensive", { expensive() });
+/// let value = profiling::scoped!("expensive", "attempt {}", 2, { expensive() + value });
+/// ```
+#[macro_export]
+macro_rules! scoped {
+    ($($args:tt)*) => {
+        $crate::__scoped!([] $($args)*)
+    };
+}
+
+// Moves tokens into the brackets until only the block is left
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __scoped {
+    ([$($args:tt)*] , $body:block) => {{
+        let _profiling_scope = $crate::scope_guard!($($args)*);
+        $body
+    }};
+    ([$($args:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__scoped!([$($args)* $next] $($rest)*)
+    };
+}
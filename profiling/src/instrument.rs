@@ -0,0 +1,106 @@
+use crate::{ScopeGuard, ScopeLocation};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// Opens a scope around each poll of a future. Holding a `scope!` guard inside an async block
+/// would also count the time spent waiting between polls, and on a multithreaded executor the
+/// scope could end on a different thread than it began on.
+///
+/// `#[profiling::function]` does the same for `async fn`, with the function name as the scope name.
+///
+/// ```
+/// use profiling::Instrument;
+///
+/// async fn load() -> u32 {
+///     42
+/// }
+///
+/// let future = load().instrument("load");
+/// ```
+pub trait Instrument: Future + Sized {
+    /// Wraps the future so every poll happens inside a scope with the given name
+    #[track_caller]
+    fn instrument(
+        self,
+        name: &'static str,
+    ) -> Instrumented<Self> {
+        Instrumented {
+            inner: self,
+            name,
+            location: ScopeLocation::caller(name),
+        }
+    }
+}
+
+impl<F: Future> Instrument for F {}
+
+/// A future returned by [`Instrument::instrument`]
+#[must_use = "futures do nothing unless polled"]
+pub struct Instrumented<F> {
+    inner: F,
+    name: &'static str,
+    location: ScopeLocation,
+}
+
+impl<F> Instrumented<F> {
+    /// Returns the wrapped future
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        // SAFETY: inner is never moved out of a pinned Instrumented, and the other fields aren't
+        // structurally pinned
+        let this = unsafe { self.get_unchecked_mut() };
+        let _scope = ScopeGuard::with_static_name(this.name, this.location);
+        unsafe { Pin::new_unchecked(&mut this.inner) }.poll(cx)
+    }
+}
+
+// Used by #[profiling::function] on async fns. The name is known when the macro expands, so each
+// poll opens its scopes through scope_guard! with a literal name and color, the same way as
+// scope! does, instead of going through a runtime name.
+#[doc(hidden)]
+pub fn __instrument_with<F, S>(
+    future: F,
+    scope: S,
+) -> InstrumentedWith<F, S>
+where
+    F: Future,
+    S: Fn() -> ScopeGuard,
+{
+    InstrumentedWith {
+        inner: future,
+        scope,
+    }
+}
+
+#[doc(hidden)]
+#[must_use = "futures do nothing unless polled"]
+pub struct InstrumentedWith<F, S> {
+    inner: F,
+    scope: S,
+}
+
+impl<F: Future, S: Fn() -> ScopeGuard> Future for InstrumentedWith<F, S> {
+    type Output = F::Output;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        // SAFETY: inner is never moved out of a pinned InstrumentedWith, and scope isn't
+        // structurally pinned
+        let this = unsafe { self.get_unchecked_mut() };
+        let _scope = (this.scope)();
+        unsafe { Pin::new_unchecked(&mut this.inner) }.poll(cx)
+    }
+}
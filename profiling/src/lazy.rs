@@ -0,0 +1,38 @@
+extern crate std;
+
+use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use std::boxed::Box;
+use std::sync::Once;
+
+// A static that is initialized on first use. OnceLock and a const Mutex::new would do the same,
+// but need a newer Rust than the MSRV.
+pub(crate) struct Lazy<T> {
+    once: Once,
+    value: AtomicPtr<T>,
+    // Only Sync if T is, which AtomicPtr alone doesn't ensure
+    _marker: PhantomData<T>,
+}
+
+impl<T> Lazy<T> {
+    pub(crate) const fn new() -> Self {
+        Lazy {
+            once: Once::new(),
+            value: AtomicPtr::new(ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn get_or_init(
+        &self,
+        init: impl FnOnce() -> T,
+    ) -> &T {
+        self.once.call_once(|| {
+            self.value
+                .store(Box::into_raw(Box::new(init())), Ordering::Release)
+        });
+        // SAFETY: call_once has stored a valid pointer by now, and it is never freed or replaced
+        unsafe { &*self.value.load(Ordering::Acquire) }
+    }
+}
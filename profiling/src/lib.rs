@@ -1,12 +1,16 @@
 //
-// To use this library, enable one of the feature flags. Each backend implementation provides the
-// exact same interface. Only one may be active at a time.
+// To use this library, enable one or more of the feature flags. Each backend implementation provides
+// the exact same interface. If several are enabled, every macro invocation is sent to all of them.
 //
 
 // This library itself does not require std, but if any features are enabled, the upstream crate
-// likely will bring in std.
+// likely will bring in std. The default `std` feature only adds `profiling::thread`.
 #![no_std]
 
+// The builtin backend records into memory, so it needs std even though the rest of the crate doesn't
+#[cfg(feature = "profile-with-builtin")]
+extern crate std;
+
 /// Proc macro for creating a scope around each function under struct impl block
 /// ```
 /// pub struct Foo {
@@ -57,6 +61,15 @@ pub use profiling_procmacros::all_functions;
 ///
 /// }
 /// ```
+///
+/// The scope can be given a color in the form 0xRRGGBB for backends that support it
+///
+/// ```
+/// #[profiling::function(color = 0xC62828)]
+/// fn my_colored_function() {
+///
+/// }
+/// ```
 #[cfg(feature = "procmacros")]
 pub use profiling_procmacros::function;
 /// Proc macro to skip the auto_impl for the function
@@ -99,6 +112,39 @@ pub use profiling_procmacros::function;
 #[cfg(feature = "procmacros")]
 pub use profiling_procmacros::skip;
 
+mod backend;
+pub use backend::*;
+
+mod location;
+pub use location::*;
+
+mod marker;
+pub use marker::*;
+
+mod field;
+pub use field::*;
+
+mod guard;
+pub use guard::*;
+
+mod manual;
+pub use manual::*;
+
+mod instrument;
+pub use instrument::*;
+
+#[cfg(any(feature = "rayon", feature = "std"))]
+mod flow;
+
+#[cfg(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-tracing",
+    feature = "profile-with-tracy",
+    feature = "profile-with-builtin"
+))]
+mod lazy;
+
 #[cfg(feature = "profile-with-puffin")]
 pub use puffin;
 #[cfg(feature = "profile-with-puffin")]
@@ -139,18 +185,67 @@ mod tracy_impl;
 #[allow(unused_imports)]
 pub use tracy_impl::*;
 
+#[cfg(feature = "profile-with-custom")]
+mod custom_impl;
+#[cfg(feature = "profile-with-custom")]
+#[allow(unused_imports)]
+pub use custom_impl::*;
+
+#[cfg(feature = "profile-with-builtin")]
+pub mod builtin;
+#[cfg(feature = "profile-with-builtin")]
+mod builtin_impl;
+#[cfg(feature = "profile-with-builtin")]
+#[allow(unused_imports)]
+pub use builtin_impl::*;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+#[cfg(feature = "rayon")]
+pub mod rayon;
+
+#[cfg(feature = "std")]
+pub mod thread;
+
 #[cfg(feature = "type-check")]
 mod type_check_impl;
 #[cfg(feature = "type-check")]
 #[allow(unused_imports)]
 pub use type_check_impl::*;
 
+#[cfg(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-superluminal",
+    feature = "profile-with-tracing",
+    feature = "profile-with-tracy",
+    feature = "profile-with-custom",
+    feature = "profile-with-builtin",
+    feature = "type-check"
+))]
+mod fanout_impl;
+#[cfg(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-superluminal",
+    feature = "profile-with-tracing",
+    feature = "profile-with-tracy",
+    feature = "profile-with-custom",
+    feature = "profile-with-builtin",
+    feature = "type-check"
+))]
+#[allow(unused_imports)]
+pub use fanout_impl::*;
+
 #[cfg(not(any(
     feature = "profile-with-puffin",
     feature = "profile-with-optick",
     feature = "profile-with-superluminal",
     feature = "profile-with-tracing",
     feature = "profile-with-tracy",
+    feature = "profile-with-custom",
+    feature = "profile-with-builtin",
     feature = "type-check"
 )))]
 mod empty_impl;
@@ -161,6 +256,8 @@ mod empty_impl;
     feature = "profile-with-superluminal",
     feature = "profile-with-tracing",
     feature = "profile-with-tracy",
+    feature = "profile-with-custom",
+    feature = "profile-with-builtin",
     feature = "type-check"
 )))]
 #[allow(unused_imports)]
@@ -0,0 +1,38 @@
+/// Where a scope was opened. This is the same information the other backends record for a scope.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScopeLocation {
+    /// Fully qualified name of the enclosing function
+    pub function_name: &'static str,
+    pub file: &'static str,
+    pub line: u32,
+}
+
+impl ScopeLocation {
+    // The location of a #[track_caller] function's caller. The enclosing function isn't known, so
+    // the scope name stands in for it.
+    #[track_caller]
+    pub(crate) fn caller(name: &'static str) -> Self {
+        let caller = core::panic::Location::caller();
+        ScopeLocation {
+            function_name: name,
+            file: caller.file(),
+            line: caller.line(),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __scope_location {
+    () => {
+        $crate::ScopeLocation {
+            function_name: {
+                struct S;
+                let type_name = core::any::type_name::<S>();
+                &type_name[..type_name.len() - 3]
+            },
+            file: file!(),
+            line: line!(),
+        }
+    };
+}
@@ -0,0 +1,147 @@
+/// Returned by [`begin_scope`]. Ending a scope through its token instead of [`end_scope`] checks,
+/// in debug builds, that it is the innermost scope still open on this thread.
+#[derive(Debug)]
+pub struct ScopeToken {
+    #[allow(dead_code)]
+    name: &'static str,
+    #[allow(dead_code)]
+    depth: usize,
+}
+
+impl ScopeToken {
+    /// Ends the scope this token was returned for
+    #[inline]
+    pub fn end(self) {
+        imp::end_scope(Some(self))
+    }
+}
+
+/// Begins a scope that stays open until a matching [`end_scope`] on the same thread. This is for
+/// callback-driven code where the start and end of the work arrive in different calls, so a
+/// `scope!` guard can't be held across them.
+///
+/// Open scopes are kept on a per-thread stack and `end_scope` always ends the most recent one.
+/// Calling `end_scope` with no open scope panics in debug builds and is ignored otherwise.
+///
+/// ```
+/// fn on_request_started() {
+///     profiling::begin_scope("request");
+/// }
+///
+/// fn on_request_finished() {
+///     profiling::end_scope();
+/// }
+///
+/// on_request_started();
+/// on_request_finished();
+///
+/// // Or end it through the returned token to check the nesting
+/// let token = profiling::begin_scope("request");
+/// token.end();
+/// ```
+#[inline]
+#[track_caller]
+pub fn begin_scope(name: &'static str) -> ScopeToken {
+    imp::begin_scope(name, crate::ScopeLocation::caller(name))
+}
+
+/// Ends the scope most recently opened with [`begin_scope`] on this thread
+#[inline]
+pub fn end_scope() {
+    imp::end_scope(None)
+}
+
+#[cfg(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-superluminal",
+    feature = "profile-with-tracing",
+    feature = "profile-with-tracy",
+    feature = "profile-with-custom",
+    feature = "profile-with-builtin",
+    feature = "type-check"
+))]
+mod imp {
+    extern crate std;
+
+    use super::ScopeToken;
+    use crate::{ScopeGuard, ScopeLocation};
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    std::thread_local! {
+        static OPEN_SCOPES: RefCell<Vec<ScopeGuard>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub(super) fn begin_scope(
+        name: &'static str,
+        location: ScopeLocation,
+    ) -> ScopeToken {
+        let guard = ScopeGuard::with_static_name(name, location);
+
+        OPEN_SCOPES.with(|scopes| {
+            let mut scopes = scopes.borrow_mut();
+            scopes.push(guard);
+            ScopeToken {
+                name,
+                depth: scopes.len() - 1,
+            }
+        })
+    }
+
+    pub(super) fn end_scope(token: Option<ScopeToken>) {
+        let depth = OPEN_SCOPES.with(|scopes| scopes.borrow().len());
+        let innermost = depth.checked_sub(1);
+
+        // Ending a scope that has inner scopes still open ends those too, innermost first, so the
+        // backends always see properly nested scopes
+        let end_at = match token {
+            Some(token) => {
+                debug_assert!(
+                    innermost == Some(token.depth),
+                    "profiling::ScopeToken::end called for \"{}\" while inner scopes are still open",
+                    token.name
+                );
+                token.depth
+            }
+            None => {
+                debug_assert!(
+                    innermost.is_some(),
+                    "profiling::end_scope called without a matching profiling::begin_scope on this thread"
+                );
+                innermost.unwrap_or(depth)
+            }
+        };
+
+        for _ in end_at..depth {
+            // The guard is dropped at the end of the loop body, outside of the borrow
+            let _scope = OPEN_SCOPES.with(|scopes| scopes.borrow_mut().pop());
+        }
+    }
+}
+
+#[cfg(not(any(
+    feature = "profile-with-puffin",
+    feature = "profile-with-optick",
+    feature = "profile-with-superluminal",
+    feature = "profile-with-tracing",
+    feature = "profile-with-tracy",
+    feature = "profile-with-custom",
+    feature = "profile-with-builtin",
+    feature = "type-check"
+)))]
+mod imp {
+    use super::ScopeToken;
+    use crate::ScopeLocation;
+
+    #[inline(always)]
+    pub(super) fn begin_scope(
+        name: &'static str,
+        _location: ScopeLocation,
+    ) -> ScopeToken {
+        ScopeToken { name, depth: 0 }
+    }
+
+    #[inline(always)]
+    pub(super) fn end_scope(_token: Option<ScopeToken>) {}
+}
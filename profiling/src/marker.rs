@@ -0,0 +1,22 @@
+/// Which part of the timeline a `marker!` applies to. Backends that can't show process or global
+/// markers record them on the current thread.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MarkerScope {
+    /// Only the thread that recorded the marker
+    Thread,
+    /// Every thread in the process
+    Process,
+    /// Everything in the trace, including other processes
+    Global,
+}
+
+impl MarkerScope {
+    /// The name used by `marker!(name, scope = ...)`, i.e. "Thread"
+    pub fn name(self) -> &'static str {
+        match self {
+            MarkerScope::Thread => "Thread",
+            MarkerScope::Process => "Process",
+            MarkerScope::Global => "Global",
+        }
+    }
+}
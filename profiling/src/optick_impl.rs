@@ -1,46 +1,172 @@
+// optick::event! declares its guard with a `let`, so these mirror its expansion but evaluate to the
+// guard. Keeping the static inside a block also allows several scopes at the same scope level.
+#[doc(hidden)]
 #[macro_export]
-macro_rules! scope {
+macro_rules! __optick_scope {
+    // The description of an optick event is created once per call site, so the format string is
+    // used as the name and the formatted name is recorded as a tag
+    (name = $fmt:literal; $name:expr) => {
+        $crate::__optick_scope!($fmt, "{}", $name)
+    };
+    // optick does not support per-scope colors
+    ($name:expr, color = $color:expr) => {
+        $crate::__optick_scope!($name)
+    };
+    ($name:expr, $data:expr, color = $color:expr) => {
+        $crate::__optick_scope!($name, $data)
+    };
+    ($name:expr, $($key:ident = $value:expr),+ $(,)?) => {{
+        let _optick_counter = $crate::__optick_scope!($name);
+        if _optick_counter.is_some() {
+            $($crate::__optick_tag!(stringify!($key), $value);)+
+        }
+        _optick_counter
+    }};
+    ($name:expr, $fmt:literal, $($arg:tt)+) => {
+        $crate::__optick_scope!($name, format!($fmt, $($arg)+))
+    };
     ($name:expr) => {
-        $crate::optick::event!($name);
+        if $crate::is_backend_enabled($crate::Backend::Optick) {
+            static mut _OPTICK_EVENT_DESCRIPTION: u64 = 0;
+            let mut _optick_counter = $crate::optick::OptickCounter { event_data: 0 };
+            unsafe {
+                if _OPTICK_EVENT_DESCRIPTION == 0 {
+                    _OPTICK_EVENT_DESCRIPTION =
+                        $crate::optick::create_description($name, file!(), line!());
+                }
+                _optick_counter.event_data = $crate::optick::push_event(_OPTICK_EVENT_DESCRIPTION);
+            }
+            Some(_optick_counter)
+        } else {
+            None
+        }
     };
     // NOTE: I've not been able to get attached data to work with optick
-    ($name:expr, $data:expr) => {
-        $crate::optick::event!($name);
-        $crate::optick::tag!("tag", $data);
+    ($name:expr, $data:expr) => {{
+        let _optick_counter = $crate::__optick_scope!($name);
+        if _optick_counter.is_some() {
+            $crate::optick::tag!("tag", $data);
+        }
+        _optick_counter
+    }};
+}
+
+// Maps a scope field to the closest of the value types optick tags support
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __optick_tag {
+    ($name:expr, $value:expr) => {
+        match $crate::AsFieldValue::as_field_value(&$value) {
+            $crate::FieldValue::I64(_value)
+                if _value >= i32::MIN as i64 && _value <= i32::MAX as i64 =>
+            {
+                $crate::optick::tag!($name, _value as i32)
+            }
+            $crate::FieldValue::I64(_value) => $crate::optick::tag!($name, _value.to_string()),
+            $crate::FieldValue::U64(_value) => $crate::optick::tag!($name, _value),
+            $crate::FieldValue::F64(_value) => $crate::optick::tag!($name, _value as f32),
+            $crate::FieldValue::Bool(_value) => {
+                $crate::optick::tag!($name, if _value { "true" } else { "false" })
+            }
+            $crate::FieldValue::Str(_value) => $crate::optick::tag!($name, _value),
+        }
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! function_scope {
+macro_rules! __optick_function_scope {
+    (color = $color:expr) => {
+        $crate::__optick_function_scope!()
+    };
+    ($data:expr, color = $color:expr) => {
+        $crate::__optick_function_scope!($data)
+    };
     () => {
-        $crate::optick::event!();
+        $crate::__optick_scope!($crate::optick::function!())
     };
     ($data:expr) => {
-        $crate::optick::event!();
-        $crate::optick::tag!("tag", $data);
+        $crate::__optick_scope!($crate::optick::function!(), $data)
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! register_thread {
+macro_rules! __optick_register_thread {
     () => {
-        let thread_name = std::thread::current()
-            .name()
-            .map(|x| x.to_string())
-            .unwrap_or_else(|| format!("Thread {:?}", std::thread::current().id()));
+        if $crate::is_backend_enabled($crate::Backend::Optick) {
+            let thread_name = std::thread::current()
+                .name()
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| format!("Thread {:?}", std::thread::current().id()));
 
-        $crate::register_thread!(&thread_name);
+            $crate::__optick_register_thread!(&thread_name);
+        }
     };
     ($name:expr) => {
-        $crate::optick::register_thread($name);
+        if $crate::is_backend_enabled($crate::Backend::Optick) {
+            $crate::optick::register_thread($name);
+        }
     };
 }
 
-/// Finishes the frame. This isn't strictly necessary for some kinds of applications but a pretty
-/// normal thing to track in games.
+#[doc(hidden)]
 #[macro_export]
-macro_rules! finish_frame {
+macro_rules! __optick_finish_frame {
     () => {
-        $crate::optick::next_frame();
+        if $crate::is_backend_enabled($crate::Backend::Optick) {
+            $crate::optick::next_frame();
+        }
+    };
+    ($name:literal) => {
+        // optick only has a single frame stream
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __optick_frame_start {
+    ($name:literal) => {
+        // optick only has a single frame stream
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __optick_frame_end {
+    ($name:literal) => {
+        // optick only has a single frame stream
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __optick_plot {
+    ($name:literal, $value:expr) => {
+        // optick has no plots
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __optick_message {
+    // optick has no messages or colors, so attach the text to the enclosing event
+    ($text:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Optick) {
+            let _text: &str = $text;
+            $crate::optick::tag!("message", _text);
+        }
+    };
+    ($text:expr, $color:expr) => {
+        $crate::__optick_message!($text)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __optick_marker {
+    // optick has no instant events, so this is a zero-length scope on the current thread
+    ($scope:ident; $($args:tt)*) => {
+        let _ = $crate::__optick_scope!($($args)*);
     };
 }
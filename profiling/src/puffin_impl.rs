@@ -1,36 +1,123 @@
+#[doc(hidden)]
 #[macro_export]
-macro_rules! scope {
+macro_rules! __puffin_scope {
+    // puffin scope names must be static, so the format string is used as the name and the
+    // formatted name is recorded as the data
+    (name = $fmt:literal; $name:expr) => {
+        $crate::__puffin_scope!($fmt, "{}", $name)
+    };
+    // puffin has no scope colors
+    ($name:expr, color = $color:expr) => {
+        $crate::__puffin_scope!($name)
+    };
+    ($name:expr, $data:expr, color = $color:expr) => {
+        $crate::__puffin_scope!($name, $data)
+    };
+    // puffin scopes only have a single data string
+    ($name:expr, $($key:ident = $value:expr),+ $(,)?) => {
+        $crate::__puffin_scope!(
+            $name,
+            $crate::DisplayFields(&$crate::__scope_fields!($($key = $value),+)).to_string()
+        )
+    };
+    // puffin only evaluates the data when scopes are on
+    ($name:expr, $fmt:literal, $($arg:tt)+) => {
+        $crate::__puffin_scope!($name, format!($fmt, $($arg)+))
+    };
     ($name:expr) => {
-        $crate::puffin::profile_scope!($name);
+        $crate::__puffin_scope!($name, "")
     };
     ($name:expr, $data:expr) => {
-        $crate::puffin::profile_scope!($name, $data);
+        if $crate::is_backend_enabled($crate::Backend::Puffin) {
+            $crate::puffin::profile_scope_custom!($name, $data)
+        } else {
+            None
+        }
     };
 }
 
+// puffin only provides profile_function! as a statement, so this uses the expression form of
+// profile_scope! named after the function. The name is evaluated inside the same two closures that
+// puffin::clean_function_name expects.
+#[doc(hidden)]
 #[macro_export]
-macro_rules! function_scope {
+macro_rules! __puffin_function_scope {
+    (color = $color:expr) => {
+        $crate::__puffin_function_scope!()
+    };
+    ($data:expr, color = $color:expr) => {
+        $crate::__puffin_function_scope!($data)
+    };
     () => {
-        $crate::puffin::profile_function!();
+        $crate::__puffin_function_scope!("")
     };
     ($data:expr) => {
-        $crate::puffin::profile_function!($data);
+        $crate::__puffin_scope!(
+            $crate::puffin::clean_function_name($crate::puffin::current_function_name!()),
+            $data
+        )
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! register_thread {
+macro_rules! __puffin_register_thread {
     () => {};
     ($name:expr) => {
         // puffin uses the thread name
     };
 }
 
-/// Finishes the frame. This isn't strictly necessary for some kinds of applications but a pretty
-/// normal thing to track in games.
+#[doc(hidden)]
 #[macro_export]
-macro_rules! finish_frame {
+macro_rules! __puffin_finish_frame {
     () => {
-        $crate::puffin::GlobalProfiler::lock().new_frame();
+        if $crate::is_backend_enabled($crate::Backend::Puffin) {
+            $crate::puffin::GlobalProfiler::lock().new_frame();
+        }
+    };
+    ($name:literal) => {
+        // puffin only has a single frame stream
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __puffin_frame_start {
+    ($name:literal) => {
+        // puffin only has a single frame stream
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __puffin_frame_end {
+    ($name:literal) => {
+        // puffin only has a single frame stream
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __puffin_plot {
+    ($name:literal, $value:expr) => {
+        // puffin has no plots
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __puffin_message {
+    ($($args:tt)*) => {
+        // puffin has no messages
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __puffin_marker {
+    // puffin has no instant events, so this is a zero-length scope on the current thread
+    ($scope:ident; $($args:tt)*) => {
+        let _ = $crate::__puffin_scope!($($args)*);
     };
 }
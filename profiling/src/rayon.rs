@@ -0,0 +1,387 @@
+//! Integration with [rayon](https://github.com/rayon-rs/rayon), enabled with the `rayon` feature.
+//!
+//! [`thread_pool_builder`] names and registers the pool's threads, and
+//! [`ScopedParallelIterator::scoped`] opens a scope around each item of a parallel iterator.
+//!
+//! ```
+//! use profiling::rayon::ScopedParallelIterator;
+//! use rayon::prelude::*;
+//!
+//! let pool = profiling::rayon::thread_pool_builder()
+//!     .num_threads(2)
+//!     .build()
+//!     .unwrap();
+//!
+//! let sizes: Vec<usize> = pool.install(|| {
+//!     profiling::scope!("Load assets");
+//!     ["a.png", "b.png", "c.png"]
+//!         .par_iter()
+//!         .scoped("Load asset")
+//!         .map(|path| path.len())
+//!         .collect()
+//! });
+//! ```
+
+extern crate std;
+
+use crate::flow::Flow;
+use crate::{ScopeGuard, ScopeLocation};
+use ::rayon::iter::plumbing::{Consumer, Folder, Producer, ProducerCallback, UnindexedConsumer};
+use ::rayon::iter::{IndexedParallelIterator, ParallelIterator};
+use ::rayon::ThreadPoolBuilder;
+use std::thread::{self, ThreadId};
+
+/// Returns a `rayon::ThreadPoolBuilder` whose threads are named "rayon-0", "rayon-1" and so on,
+/// and call `register_thread!` with that name when they start. This replaces the builder's
+/// `thread_name` and `start_handler`.
+pub fn thread_pool_builder() -> ThreadPoolBuilder {
+    ThreadPoolBuilder::new()
+        .thread_name(thread_name)
+        .start_handler(|index| {
+            // Unused when no backend is enabled
+            #[allow(unused_variables)]
+            let name = thread_name(index);
+            crate::register_thread!(&name);
+        })
+}
+
+fn thread_name(index: usize) -> std::string::String {
+    std::format!("rayon-{}", index)
+}
+
+/// Adds [`scoped`](Self::scoped) to every parallel iterator
+pub trait ScopedParallelIterator: ParallelIterator {
+    /// Opens a scope with the given name around each item, covering everything the rest of the
+    /// chain does with it. When part of the work runs on a different thread than the one that
+    /// started the iterator, the first scope on that thread is linked back to the scope that was
+    /// open when the iterator started. Builtin captures show these links as flows, and tracing as
+    /// `follows_from`.
+    ///
+    /// The result is indexed if `self` is. When a later adaptor pulls the items one at a time
+    /// instead of passing them down the chain, as `zip` does, the scope only covers producing each
+    /// item.
+    #[track_caller]
+    fn scoped(
+        self,
+        name: &'static str,
+    ) -> Scoped<Self> {
+        Scoped {
+            base: self,
+            name,
+            location: ScopeLocation::caller(name),
+        }
+    }
+}
+
+impl<I: ParallelIterator> ScopedParallelIterator for I {}
+
+/// A parallel iterator returned by [`ScopedParallelIterator::scoped`]
+#[must_use = "iterator adaptors are lazy and do nothing unless consumed"]
+pub struct Scoped<I> {
+    base: I,
+    name: &'static str,
+    location: ScopeLocation,
+}
+
+impl<I: ParallelIterator> ParallelIterator for Scoped<I> {
+    type Item = I::Item;
+
+    fn drive_unindexed<C>(
+        self,
+        consumer: C,
+    ) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let shared = Shared::new(self.name, self.location);
+        self.base.drive_unindexed(ScopedConsumer {
+            base: consumer,
+            shared: &shared,
+        })
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        self.base.opt_len()
+    }
+}
+
+impl<I: IndexedParallelIterator> IndexedParallelIterator for Scoped<I> {
+    fn drive<C>(
+        self,
+        consumer: C,
+    ) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        let shared = Shared::new(self.name, self.location);
+        self.base.drive(ScopedConsumer {
+            base: consumer,
+            shared: &shared,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+
+    fn with_producer<CB>(
+        self,
+        callback: CB,
+    ) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        let shared = Shared::new(self.name, self.location);
+        self.base.with_producer(ScopedCallback {
+            base: callback,
+            shared: &shared,
+        })
+    }
+}
+
+// Everything the consumers and producers split off from each other have in common
+struct Shared {
+    name: &'static str,
+    location: ScopeLocation,
+    origin: ThreadId,
+    flow: Flow,
+}
+
+impl Shared {
+    // Called inside the scope that was open when the iterator started
+    fn new(
+        name: &'static str,
+        location: ScopeLocation,
+    ) -> Self {
+        Shared {
+            name,
+            location,
+            origin: thread::current().id(),
+            flow: Flow::begin(),
+        }
+    }
+
+    // Whether a job starting on the current thread doesn't need a link back to the origin
+    fn linked(&self) -> bool {
+        thread::current().id() == self.origin
+    }
+
+    // Opens the scope for one item, linking it back to the origin if it is the first one of a job
+    // that needs it
+    fn scope(
+        &self,
+        linked: &mut bool,
+    ) -> ScopeGuard {
+        let scope = ScopeGuard::with_static_name(self.name, self.location);
+        if !*linked {
+            self.flow.end();
+            *linked = true;
+        }
+        scope
+    }
+}
+
+struct ScopedConsumer<'a, C> {
+    base: C,
+    shared: &'a Shared,
+}
+
+impl<'a, T, C: Consumer<T>> Consumer<T> for ScopedConsumer<'a, C> {
+    type Folder = ScopedFolder<'a, C::Folder>;
+    type Reducer = C::Reducer;
+    type Result = C::Result;
+
+    fn split_at(
+        self,
+        index: usize,
+    ) -> (Self, Self, Self::Reducer) {
+        let (left, right, reducer) = self.base.split_at(index);
+        (
+            ScopedConsumer {
+                base: left,
+                shared: self.shared,
+            },
+            ScopedConsumer {
+                base: right,
+                shared: self.shared,
+            },
+            reducer,
+        )
+    }
+
+    // Folders are created on the thread that runs the job
+    fn into_folder(self) -> Self::Folder {
+        ScopedFolder {
+            base: self.base.into_folder(),
+            shared: self.shared,
+            linked: self.shared.linked(),
+        }
+    }
+
+    fn full(&self) -> bool {
+        self.base.full()
+    }
+}
+
+impl<'a, T, C: UnindexedConsumer<T>> UnindexedConsumer<T> for ScopedConsumer<'a, C> {
+    fn split_off_left(&self) -> Self {
+        ScopedConsumer {
+            base: self.base.split_off_left(),
+            shared: self.shared,
+        }
+    }
+
+    fn to_reducer(&self) -> Self::Reducer {
+        self.base.to_reducer()
+    }
+}
+
+struct ScopedFolder<'a, F> {
+    base: F,
+    shared: &'a Shared,
+    // Whether this job already has a link back to the origin, or doesn't need one
+    linked: bool,
+}
+
+impl<'a, T, F: Folder<T>> Folder<T> for ScopedFolder<'a, F> {
+    type Result = F::Result;
+
+    fn consume(
+        mut self,
+        item: T,
+    ) -> Self {
+        let _scope = self.shared.scope(&mut self.linked);
+        ScopedFolder {
+            base: self.base.consume(item),
+            shared: self.shared,
+            linked: self.linked,
+        }
+    }
+
+    fn complete(self) -> Self::Result {
+        self.base.complete()
+    }
+
+    fn full(&self) -> bool {
+        self.base.full()
+    }
+}
+
+struct ScopedCallback<'a, CB> {
+    base: CB,
+    shared: &'a Shared,
+}
+
+impl<'a, T, CB: ProducerCallback<T>> ProducerCallback<T> for ScopedCallback<'a, CB> {
+    type Output = CB::Output;
+
+    fn callback<P>(
+        self,
+        producer: P,
+    ) -> Self::Output
+    where
+        P: Producer<Item = T>,
+    {
+        self.base.callback(ScopedProducer {
+            base: producer,
+            shared: self.shared,
+        })
+    }
+}
+
+struct ScopedProducer<'a, P> {
+    base: P,
+    shared: &'a Shared,
+}
+
+impl<'a, P: Producer> Producer for ScopedProducer<'a, P> {
+    type Item = P::Item;
+    type IntoIter = ScopedIter<'a, P::IntoIter>;
+
+    // Iterators and folders are created on the thread that runs the job
+    fn into_iter(self) -> Self::IntoIter {
+        ScopedIter {
+            base: self.base.into_iter(),
+            shared: self.shared,
+            linked: self.shared.linked(),
+        }
+    }
+
+    fn min_len(&self) -> usize {
+        self.base.min_len()
+    }
+
+    fn max_len(&self) -> usize {
+        self.base.max_len()
+    }
+
+    fn split_at(
+        self,
+        index: usize,
+    ) -> (Self, Self) {
+        let (left, right) = self.base.split_at(index);
+        (
+            ScopedProducer {
+                base: left,
+                shared: self.shared,
+            },
+            ScopedProducer {
+                base: right,
+                shared: self.shared,
+            },
+        )
+    }
+
+    // Adaptors after this one pass their folder in here, so the scope covers them as well
+    fn fold_with<F>(
+        self,
+        folder: F,
+    ) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        self.base
+            .fold_with(ScopedFolder {
+                base: folder,
+                shared: self.shared,
+                linked: self.shared.linked(),
+            })
+            .base
+    }
+}
+
+// Used when the items are pulled one at a time, so each scope only covers producing the item
+struct ScopedIter<'a, I> {
+    base: I,
+    shared: &'a Shared,
+    linked: bool,
+}
+
+impl<'a, I: Iterator> Iterator for ScopedIter<'a, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Producers' iterators know their exact length, so no scope is opened for the final None
+        if self.base.size_hint().1 == Some(0) {
+            return None;
+        }
+        let _scope = self.shared.scope(&mut self.linked);
+        self.base.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.base.size_hint()
+    }
+}
+
+impl<'a, I: DoubleEndedIterator> DoubleEndedIterator for ScopedIter<'a, I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.base.size_hint().1 == Some(0) {
+            return None;
+        }
+        let _scope = self.shared.scope(&mut self.linked);
+        self.base.next_back()
+    }
+}
+
+impl<'a, I: ExactSizeIterator> ExactSizeIterator for ScopedIter<'a, I> {}
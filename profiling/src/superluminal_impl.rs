@@ -1,56 +1,156 @@
+#[doc(hidden)]
 #[macro_export]
-macro_rules! scope {
+macro_rules! __superluminal_scope {
+    // superluminal event IDs must be static, so the format string is used as the name and the
+    // formatted name is recorded as the data
+    (name = $fmt:literal; $name:expr) => {
+        $crate::__superluminal_scope!($fmt, "{}", $name)
+    };
+    ($name:expr, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Superluminal) {
+            Some($crate::superluminal::SuperluminalGuard::new_with_color(
+                $name, None, $color,
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $data:expr, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Superluminal) {
+            Some($crate::superluminal::SuperluminalGuard::new_with_color(
+                $name,
+                Some($data),
+                $color,
+            ))
+        } else {
+            None
+        }
+    };
+    // superluminal events only have a single data string
+    ($name:expr, $($key:ident = $value:expr),+ $(,)?) => {
+        if $crate::is_backend_enabled($crate::Backend::Superluminal) {
+            Some($crate::superluminal::SuperluminalGuard::new_with_data(
+                $name,
+                &$crate::DisplayFields(&$crate::__scope_fields!($($key = $value),+)).to_string(),
+            ))
+        } else {
+            None
+        }
+    };
+    ($name:expr, $fmt:literal, $($arg:tt)+) => {
+        if $crate::is_backend_enabled($crate::Backend::Superluminal) {
+            // The API does nothing on platforms superluminal doesn't support, so don't format there
+            if $crate::superluminal_perf::enabled() {
+                Some($crate::superluminal::SuperluminalGuard::new_with_data(
+                    $name,
+                    &format!($fmt, $($arg)+),
+                ))
+            } else {
+                Some($crate::superluminal::SuperluminalGuard::new($name))
+            }
+        } else {
+            None
+        }
+    };
     ($name:expr) => {
-        let _superluminal_guard = $crate::superluminal::SuperluminalGuard::new($name);
+        if $crate::is_backend_enabled($crate::Backend::Superluminal) {
+            Some($crate::superluminal::SuperluminalGuard::new($name))
+        } else {
+            None
+        }
     };
     ($name:expr, $data:expr) => {
-        let _superluminal_guard =
-            $crate::superluminal::SuperluminalGuard::new_with_data($name, $data);
+        if $crate::is_backend_enabled($crate::Backend::Superluminal) {
+            Some($crate::superluminal::SuperluminalGuard::new_with_data(
+                $name, $data,
+            ))
+        } else {
+            None
+        }
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! function_scope {
-    () => {
+macro_rules! __superluminal_function_scope {
+    (color = $color:expr) => {{
         let _function_name = {
             struct S;
             let type_name = core::any::type_name::<S>();
             &type_name[..type_name.len() - 3]
         };
-        $crate::scope!(_function_name);
-    };
-    ($data:expr) => {
+        $crate::__superluminal_scope!(_function_name, color = $color)
+    }};
+    ($data:expr, color = $color:expr) => {{
         let _function_name = {
             struct S;
             let type_name = core::any::type_name::<S>();
             &type_name[..type_name.len() - 3]
         };
-        $crate::scope!(_function_name, $data);
-    };
+        $crate::__superluminal_scope!(_function_name, $data, color = $color)
+    }};
+    () => {{
+        let _function_name = {
+            struct S;
+            let type_name = core::any::type_name::<S>();
+            &type_name[..type_name.len() - 3]
+        };
+        $crate::__superluminal_scope!(_function_name)
+    }};
+    ($data:expr) => {{
+        let _function_name = {
+            struct S;
+            let type_name = core::any::type_name::<S>();
+            &type_name[..type_name.len() - 3]
+        };
+        $crate::__superluminal_scope!(_function_name, $data)
+    }};
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! register_thread {
+macro_rules! __superluminal_register_thread {
     () => {
-        let thread_name = std::thread::current()
-            .name()
-            .map(|x| x.to_string())
-            .unwrap_or_else(|| format!("Thread {:?}", std::thread::current().id()));
+        if $crate::is_backend_enabled($crate::Backend::Superluminal) {
+            let thread_name = std::thread::current()
+                .name()
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| format!("Thread {:?}", std::thread::current().id()));
 
-        $crate::register_thread!(&thread_name);
+            $crate::__superluminal_register_thread!(&thread_name);
+        }
     };
     ($name:expr) => {
-        $crate::superluminal_perf::set_current_thread_name($name);
+        if $crate::is_backend_enabled($crate::Backend::Superluminal) {
+            $crate::superluminal_perf::set_current_thread_name($name);
+        }
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! finish_frame {
-    () => {
+macro_rules! __superluminal_finish_frame {
+    ($($name:literal)?) => {
         // superluminal does not have a frame end function
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __superluminal_frame_start {
+    ($name:literal) => {
+        // superluminal does not have frames
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __superluminal_frame_end {
+    ($name:literal) => {
+        // superluminal does not have frames
+    };
+}
+
 //
 // RAII wrappers to support superluminal. These are public as they need to be callable from macros
 // but are not intended for direct use.
@@ -75,6 +175,20 @@ pub mod superluminal {
             superluminal_perf::begin_event_with_data(name, data, DEFAULT_SUPERLUMINAL_COLOR);
             SuperluminalGuard
         }
+
+        pub fn new_with_color(
+            name: &'static str,
+            data: Option<&str>,
+            color: u32,
+        ) -> Self {
+            // Superluminal colors are 0xRRGGBBAA
+            let color = color << 8 | 0xFF;
+            match data {
+                Some(data) => superluminal_perf::begin_event_with_data(name, data, color),
+                None => superluminal_perf::begin_event_with_color(name, color),
+            }
+            SuperluminalGuard
+        }
     }
 
     impl Drop for SuperluminalGuard {
@@ -83,3 +197,28 @@ pub mod superluminal {
         }
     }
 }
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __superluminal_plot {
+    ($name:literal, $value:expr) => {
+        // superluminal has no plots
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __superluminal_message {
+    ($($args:tt)*) => {
+        // superluminal has no messages
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __superluminal_marker {
+    // superluminal has no instant events, so this is a zero-length scope on the current thread
+    ($scope:ident; $($args:tt)*) => {
+        let _ = $crate::__superluminal_scope!($($args)*);
+    };
+}
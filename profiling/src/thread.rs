@@ -0,0 +1,188 @@
+//! Replacements for `std::thread::spawn` and `std::thread::Builder` that call `register_thread!` on
+//! the new thread before running the closure. The scope that was open on the parent thread when the
+//! thread was spawned is linked to the first scope of the new thread, which builtin captures show
+//! as a flow. With tracing, the new thread runs inside a `thread` span that follows from it.
+//!
+//! ```
+//! let handle = profiling::thread::Builder::new()
+//!     .name("Loader".to_string())
+//!     .spawn(|| {
+//!         profiling::scope!("Load");
+//!         42
+//!     })
+//!     .unwrap();
+//! assert_eq!(handle.join().unwrap(), 42);
+//!
+//! // Unnamed threads are registered as "Thread 1", "Thread 2" and so on
+//! profiling::thread::spawn(|| {}).join().unwrap();
+//! ```
+
+extern crate std;
+
+use crate::flow::Flow;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::io;
+use std::string::String;
+use std::thread::JoinHandle;
+
+/// Like `std::thread::Builder`, but the thread is registered with the profiler
+#[derive(Debug)]
+pub struct Builder {
+    inner: std::thread::Builder,
+    name: Option<String>,
+}
+
+// Starts at 1 to match how people count threads
+static NEXT_UNNAMED_INDEX: AtomicUsize = AtomicUsize::new(1);
+
+impl Builder {
+    pub fn new() -> Self {
+        Builder {
+            inner: std::thread::Builder::new(),
+            name: None,
+        }
+    }
+
+    /// Names the thread, both for the profiler and for `std::thread::Thread::name`
+    pub fn name(
+        self,
+        name: String,
+    ) -> Self {
+        Builder {
+            inner: self.inner.name(name.clone()),
+            name: Some(name),
+        }
+    }
+
+    /// Sets the size of the new thread's stack in bytes
+    pub fn stack_size(
+        self,
+        size: usize,
+    ) -> Self {
+        Builder {
+            inner: self.inner.stack_size(size),
+            name: self.name,
+        }
+    }
+
+    /// Spawns the thread like `std::thread::Builder::spawn`
+    pub fn spawn<F, T>(
+        self,
+        f: F,
+    ) -> io::Result<JoinHandle<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        // Unused when no backend is enabled
+        #[allow(unused_variables)]
+        let name = self.name.unwrap_or_else(|| {
+            std::format!(
+                "Thread {}",
+                NEXT_UNNAMED_INDEX.fetch_add(1, Ordering::Relaxed)
+            )
+        });
+        let flow = Flow::begin();
+        self.inner.spawn(move || {
+            crate::register_thread!(&name);
+            #[cfg(feature = "profile-with-tracing")]
+            let _span = flow.thread_span(&name);
+            flow.end();
+            f()
+        })
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder::new()
+    }
+}
+
+/// Spawns a thread like `std::thread::spawn`, registered with the profiler as "Thread 1",
+/// "Thread 2" and so on. Use [`Builder`] to give it a name.
+///
+/// # Panics
+///
+/// Panics if the OS fails to create a thread, like `std::thread::spawn`
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Builder::new().spawn(f).expect("failed to spawn thread")
+}
+
+#[cfg(all(test, feature = "profile-with-tracing"))]
+mod tests {
+    extern crate std;
+
+    use std::string::String;
+    use std::sync::{Arc, Mutex};
+    use std::vec::Vec;
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    type Shared<T> = Arc<Mutex<Vec<T>>>;
+
+    // Records the name of each span with the name of its parent, and each follows_from link by name
+    #[derive(Clone, Default)]
+    struct Recorder {
+        parents: Shared<(&'static str, Option<&'static str>)>,
+        links: Shared<(&'static str, &'static str)>,
+    }
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+        fn on_new_span(
+            &self,
+            _attrs: &Attributes<'_>,
+            id: &Id,
+            ctx: Context<'_, S>,
+        ) {
+            let span = ctx.span(id).unwrap();
+            let parent = span.parent().map(|parent| parent.name());
+            self.parents.lock().unwrap().push((span.name(), parent));
+        }
+
+        fn on_follows_from(
+            &self,
+            id: &Id,
+            follows: &Id,
+            ctx: Context<'_, S>,
+        ) {
+            let span = ctx.span(id).unwrap().name();
+            let follows = ctx.span(follows).unwrap().name();
+            self.links.lock().unwrap().push((span, follows));
+        }
+    }
+
+    #[test]
+    fn spawned_thread_follows_from_parent_span() {
+        // The spawned thread doesn't inherit a thread-local subscriber
+        let recorder = Recorder::default();
+        tracing::subscriber::set_global_default(
+            tracing_subscriber::registry().with(recorder.clone()),
+        )
+        .unwrap();
+
+        {
+            crate::scope!("spawn loader");
+            super::Builder::new()
+                .name(String::from("Loader"))
+                .spawn(|| {
+                    crate::scope!("load");
+                })
+                .unwrap()
+                .join()
+                .unwrap();
+        }
+
+        let links = recorder.links.lock().unwrap();
+        assert!(links.contains(&("thread", "spawn loader")));
+        let parents = recorder.parents.lock().unwrap();
+        assert!(parents.contains(&("load", Some("thread"))));
+    }
+}
@@ -0,0 +1,144 @@
+//! Integration with the [tokio](https://tokio.rs) runtime, enabled with the `tokio` feature.
+//!
+//! [`register_threads`] names the runtime's threads in the profiler and [`spawn`] records each
+//! poll of a task as a scope named after it. The builtin, custom and tracing backends also get the
+//! lifetime of each task. The builtin exports draw it on a track of its own. tracy and the other
+//! backends only have tracks per thread, so they show the polls on whichever threads ran them.
+//!
+//! ```
+//! let runtime = profiling::tokio::register_threads(
+//!     tokio::runtime::Builder::new_multi_thread().worker_threads(2),
+//! )
+//! .build()
+//! .unwrap();
+//!
+//! runtime.block_on(async {
+//!     let handle = profiling::tokio::spawn("load_level", async { 42 });
+//!     assert_eq!(handle.await.unwrap(), 42);
+//! });
+//! ```
+
+extern crate std;
+
+use crate::{Instrument, Instrumented};
+use ::tokio::runtime::Builder;
+use ::tokio::task::JoinHandle;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::task::{Context, Poll};
+use std::sync::Arc;
+
+/// Calls `register_thread!` on every thread the runtime starts, named after the thread and the
+/// order it started in, i.e. "tokio-rt-worker-3". This replaces any `on_thread_start`
+/// callback set on the builder before. With a current-thread runtime, only the threads of the
+/// blocking pool are started by the runtime.
+pub fn register_threads(builder: &mut Builder) -> &mut Builder {
+    let next_index = Arc::new(AtomicUsize::new(0));
+    builder.on_thread_start(move || {
+        // Unused when no backend is enabled
+        #[allow(unused_variables)]
+        let name = std::format!(
+            "{}-{}",
+            std::thread::current().name().unwrap_or("tokio"),
+            next_index.fetch_add(1, Ordering::Relaxed)
+        );
+        crate::register_thread!(&name);
+    })
+}
+
+/// Spawns a task like `tokio::spawn`, wrapped with [`task`]
+#[track_caller]
+pub fn spawn<F>(
+    name: &'static str,
+    future: F,
+) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    ::tokio::spawn(task(name, future))
+}
+
+/// Wraps a future that is about to be spawned, i.e. with `Handle::spawn` or a `JoinSet`. Each
+/// poll is recorded as a scope with the given name, and the task ends when the returned future
+/// completes or is dropped.
+#[track_caller]
+pub fn task<F: Future>(
+    name: &'static str,
+    future: F,
+) -> Task<F> {
+    let id = NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed);
+    task_begin(id, name);
+    Task {
+        inner: future.instrument(name),
+        id,
+        #[cfg(feature = "profile-with-tracing")]
+        span: if crate::is_backend_enabled(crate::Backend::Tracing) {
+            tracing::span!(tracing::Level::INFO, "task", name = name, id = id)
+        } else {
+            tracing::Span::none()
+        },
+    }
+}
+
+// Starts at 1 so that 0 is never a valid id
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A future returned by [`task`]
+#[must_use = "futures do nothing unless polled"]
+pub struct Task<F> {
+    inner: Instrumented<F>,
+    id: u64,
+    #[cfg(feature = "profile-with-tracing")]
+    span: tracing::Span,
+}
+
+impl<F: Future> Future for Task<F> {
+    type Output = F::Output;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        // SAFETY: inner is never moved out of a pinned Task, and the other fields aren't
+        // structurally pinned
+        let this = unsafe { self.get_unchecked_mut() };
+        #[cfg(feature = "profile-with-tracing")]
+        let _entered = this.span.enter();
+        unsafe { Pin::new_unchecked(&mut this.inner) }.poll(cx)
+    }
+}
+
+impl<F> Drop for Task<F> {
+    fn drop(&mut self) {
+        task_end(self.id);
+    }
+}
+
+#[allow(unused_variables)]
+fn task_begin(
+    id: u64,
+    name: &'static str,
+) {
+    #[cfg(feature = "profile-with-custom")]
+    if let Some(backend) = crate::custom::enabled_backend() {
+        backend.task_begin(id, name);
+    }
+    #[cfg(feature = "profile-with-builtin")]
+    if crate::is_backend_enabled(crate::Backend::Builtin) {
+        crate::builtin::task_begin(id, name);
+    }
+}
+
+#[allow(unused_variables)]
+fn task_end(id: u64) {
+    #[cfg(feature = "profile-with-custom")]
+    if let Some(backend) = crate::custom::enabled_backend() {
+        backend.task_end(id);
+    }
+    #[cfg(feature = "profile-with-builtin")]
+    if crate::is_backend_enabled(crate::Backend::Builtin) {
+        crate::builtin::task_end(id);
+    }
+}
@@ -1,57 +1,263 @@
+#[doc(hidden)]
 #[macro_export]
-macro_rules! scope {
+macro_rules! __tracing_scope {
+    // Span names must be static, so the format string is used as the name and the formatted name
+    // is recorded as a field. Fields are only formatted if a subscriber records them.
+    (name = $fmt:literal; $name:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::span!(
+                $crate::tracing::Level::INFO,
+                $fmt,
+                name = %$name
+            )
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
+    };
+    ($name:expr, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::span!($crate::tracing::Level::INFO, $name, color = $color)
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
+    };
+    ($name:expr, $data:expr, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::span!(
+                $crate::tracing::Level::INFO,
+                $name,
+                tag = $data,
+                color = $color
+            )
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
+    };
+    ($name:expr, $($key:ident = $value:expr),+ $(,)?) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::span!(
+                $crate::tracing::Level::INFO,
+                $name,
+                $($key = $crate::AsTracingValue::as_tracing_value(&$value)),+
+            )
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
+    };
+    ($name:expr, $fmt:literal, $($arg:tt)+) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::span!(
+                $crate::tracing::Level::INFO,
+                $name,
+                tag = %format_args!($fmt, $($arg)+)
+            )
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
+    };
     ($name:expr) => {
-        let _span = $crate::tracing::span!($crate::tracing::Level::INFO, $name);
-        let _span_entered = _span.enter();
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::span!($crate::tracing::Level::INFO, $name)
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
     };
     ($name:expr, $data:expr) => {
-        let _span = $crate::tracing::span!($crate::tracing::Level::INFO, $name, tag = $data);
-        let _span_entered = _span.enter();
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::span!($crate::tracing::Level::INFO, $name, tag = $data)
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! function_scope {
+macro_rules! __tracing_function_scope {
+    (color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            let function_name = {
+                struct S;
+                let type_name = core::any::type_name::<S>();
+                &type_name[..type_name.len() - 3]
+            };
+            $crate::tracing::span!(
+                $crate::tracing::Level::INFO,
+                "function_scope",
+                color = $color,
+                "{}",
+                function_name
+            )
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
+    };
+    ($data:expr, color = $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            let function_name = {
+                struct S;
+                let type_name = core::any::type_name::<S>();
+                &type_name[..type_name.len() - 3]
+            };
+            $crate::tracing::span!(
+                $crate::tracing::Level::INFO,
+                "function_scope",
+                tag = $data,
+                color = $color,
+                "{}",
+                function_name
+            )
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
+    };
     () => {
-        let function_name = {
-            struct S;
-            let type_name = core::any::type_name::<S>();
-            &type_name[..type_name.len() - 3]
-        };
-        let _span = $crate::tracing::span!(
-            $crate::tracing::Level::INFO,
-            "function_scope",
-            "{}",
-            function_name
-        );
-        let _span_entered = _span.enter();
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            let function_name = {
+                struct S;
+                let type_name = core::any::type_name::<S>();
+                &type_name[..type_name.len() - 3]
+            };
+            $crate::tracing::span!(
+                $crate::tracing::Level::INFO,
+                "function_scope",
+                "{}",
+                function_name
+            )
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
     };
     ($data:expr) => {
-        let function_name = {
-            struct S;
-            let type_name = core::any::type_name::<S>();
-            &type_name[..type_name.len() - 3]
-        };
-        let _span = $crate::tracing::span!(
-            $crate::tracing::Level::INFO,
-            "function_scope",
-            tag = $data,
-            "{}",
-            function_name
-        );
-        let _span_entered = _span.enter();
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            let function_name = {
+                struct S;
+                let type_name = core::any::type_name::<S>();
+                &type_name[..type_name.len() - 3]
+            };
+            $crate::tracing::span!(
+                $crate::tracing::Level::INFO,
+                "function_scope",
+                tag = $data,
+                "{}",
+                function_name
+            )
+        } else {
+            $crate::tracing::Span::none()
+        }
+        .entered()
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! register_thread {
+macro_rules! __tracing_register_thread {
     () => {};
     ($name:expr) => {};
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! finish_frame {
+macro_rules! __tracing_finish_frame {
     () => {
-        $crate::tracing::event!($crate::tracing::Level::INFO, tracy.frame_mark = true);
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::event!($crate::tracing::Level::INFO, tracy.frame_mark = true);
+        }
+    };
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::event!($crate::tracing::Level::INFO, frame_mark = $name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracing_frame_start {
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::event!($crate::tracing::Level::INFO, frame_start = $name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracing_frame_end {
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::event!($crate::tracing::Level::INFO, frame_end = $name);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracing_plot {
+    ($name:literal, $value:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            $crate::tracing::event!(
+                $crate::tracing::Level::INFO,
+                plot = $name,
+                value = $value as f64
+            );
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracing_message {
+    ($text:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            let _text: &str = $text;
+            $crate::tracing::event!($crate::tracing::Level::INFO, "{}", _text);
+        }
+    };
+    ($text:expr, $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            let _text: &str = $text;
+            let _color: u32 = $color;
+            $crate::tracing::event!($crate::tracing::Level::INFO, color = _color, "{}", _text);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracing_marker {
+    ($scope:ident; $name:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            let _name: &str = $name;
+            $crate::tracing::event!(
+                $crate::tracing::Level::INFO,
+                marker_scope = $crate::MarkerScope::$scope.name(),
+                "{}",
+                _name
+            );
+        }
+    };
+    ($scope:ident; $name:expr, $data:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracing) {
+            let _name: &str = $name;
+            let _data: &str = $data;
+            $crate::tracing::event!(
+                $crate::tracing::Level::INFO,
+                marker_scope = $crate::MarkerScope::$scope.name(),
+                tag = _data,
+                "{}",
+                _name
+            );
+        }
     };
 }
@@ -1,82 +1,364 @@
+extern crate std;
+
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use std::collections::BTreeMap;
+use std::os::raw::c_char;
+use std::sync::{Mutex, PoisonError};
+
+// tracy tells frame sets apart by the address of their name, but identical literals at different
+// call sites (i.e. in frame_start! and frame_end!) aren't guaranteed to share an address. Each call
+// site keeps a TracyFrameName that looks its name up once and then reuses the first address that
+// was seen for it.
+#[doc(hidden)]
+pub struct TracyFrameName(AtomicPtr<c_char>);
+
+static FRAME_NAMES: crate::lazy::Lazy<Mutex<BTreeMap<&'static str, &'static str>>> =
+    crate::lazy::Lazy::new();
+
+impl TracyFrameName {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        TracyFrameName(AtomicPtr::new(ptr::null_mut()))
+    }
+
+    // The name must be null-terminated
+    pub fn get(
+        &self,
+        name: &'static str,
+    ) -> *const c_char {
+        let cached = self.0.load(Ordering::Relaxed);
+        if !cached.is_null() {
+            return cached;
+        }
+        let interned = *FRAME_NAMES
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(name)
+            .or_insert(name);
+        let interned = interned.as_ptr() as *mut c_char;
+        self.0.store(interned, Ordering::Relaxed);
+        interned
+    }
+}
+
+#[doc(hidden)]
 #[macro_export]
-macro_rules! scope {
+macro_rules! __tracy_scope {
+    // Only formats the name while a profiler is connected, otherwise the format string is used
+    (name = $fmt:literal; $name:expr) => {
+        if $crate::tracy_client::Client::is_connected() {
+            $crate::__tracy_scope!(&format!("{}", $name))
+        } else {
+            $crate::__tracy_scope!($fmt)
+        }
+    };
     // Note: literal patterns provided as an optimization since they can skip an allocation.
+    ($name:literal, color = $color:expr) => {{
+        let _tracy_span = $crate::__tracy_scope!($name);
+        if let Some(_tracy_span) = &_tracy_span {
+            _tracy_span.emit_color($color);
+        }
+        _tracy_span
+    }};
+    ($name:literal, $data:expr, color = $color:expr) => {{
+        let _tracy_span = $crate::__tracy_scope!($name, $data);
+        if let Some(_tracy_span) = &_tracy_span {
+            _tracy_span.emit_color($color);
+        }
+        _tracy_span
+    }};
+    ($name:expr, color = $color:expr) => {{
+        let _tracy_span = $crate::__tracy_scope!($name);
+        if let Some(_tracy_span) = &_tracy_span {
+            _tracy_span.emit_color($color);
+        }
+        _tracy_span
+    }};
+    ($name:expr, $data:expr, color = $color:expr) => {{
+        let _tracy_span = $crate::__tracy_scope!($name, $data);
+        if let Some(_tracy_span) = &_tracy_span {
+            _tracy_span.emit_color($color);
+        }
+        _tracy_span
+    }};
+    ($name:literal, $($key:ident = $value:expr),+ $(,)?) => {{
+        let _tracy_span = $crate::__tracy_scope!($name);
+        if let Some(_tracy_span) = &_tracy_span {
+            $($crate::__tracy_field!(_tracy_span, $key, $value);)+
+        }
+        _tracy_span
+    }};
+    ($name:expr, $($key:ident = $value:expr),+ $(,)?) => {{
+        let _tracy_span = $crate::__tracy_scope!($name);
+        if let Some(_tracy_span) = &_tracy_span {
+            $($crate::__tracy_field!(_tracy_span, $key, $value);)+
+        }
+        _tracy_span
+    }};
+    ($name:literal, $fmt:literal, $($arg:tt)+) => {{
+        let _tracy_span = $crate::__tracy_scope!($name);
+        if let Some(_tracy_span) = &_tracy_span {
+            if $crate::tracy_client::Client::is_connected() {
+                _tracy_span.emit_text(&format!($fmt, $($arg)+));
+            }
+        }
+        _tracy_span
+    }};
+    ($name:expr, $fmt:literal, $($arg:tt)+) => {{
+        let _tracy_span = $crate::__tracy_scope!($name);
+        if let Some(_tracy_span) = &_tracy_span {
+            if $crate::tracy_client::Client::is_connected() {
+                _tracy_span.emit_text(&format!($fmt, $($arg)+));
+            }
+        }
+        _tracy_span
+    }};
     ($name:literal) => {
-        // Note: callstack_depth is 0 since this has significant overhead
-        let _tracy_span = $crate::tracy_client::span!($name, 0);
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            // Note: callstack_depth is 0 since this has significant overhead
+            Some($crate::tracy_client::span!($name, 0))
+        } else {
+            None
+        }
     };
     ($name:literal, $data:expr) => {
-        // Note: callstack_depth is 0 since this has significant overhead
-        let _tracy_span = $crate::tracy_client::span!($name, 0);
-        _tracy_span.emit_text($data);
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            // Note: callstack_depth is 0 since this has significant overhead
+            let _tracy_span = $crate::tracy_client::span!($name, 0);
+            _tracy_span.emit_text($data);
+            Some(_tracy_span)
+        } else {
+            None
+        }
     };
     ($name:expr) => {
-        let _function_name = {
-            struct S;
-            let type_name = core::any::type_name::<S>();
-            &type_name[..type_name.len() - 3]
-        };
-        let _tracy_span = $crate::tracy_client::Client::running()
-            .expect("scope! without a running tracy_client::Client")
-            // Note: callstack_depth is 0 since this has significant overhead
-            .span_alloc(Some($name), _function_name, file!(), line!(), 0);
-    };
-    ($name:expr, $data:expr) => {
-        let _function_name = {
-            struct S;
-            let type_name = core::any::type_name::<S>();
-            &type_name[..type_name.len() - 3]
-        };
-        let _tracy_span = $crate::tracy_client::Client::running()
-            .expect("scope! without a running tracy_client::Client")
-            // Note: callstack_depth is 0 since this has significant overhead
-            .span_alloc(Some($name), _function_name, file!(), line!(), 0);
-        _tracy_span.emit_text($data);
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            let _function_name = {
+                struct S;
+                let type_name = core::any::type_name::<S>();
+                &type_name[..type_name.len() - 3]
+            };
+            Some(
+                $crate::tracy_client::Client::running()
+                    .expect("scope! without a running tracy_client::Client")
+                    // Note: callstack_depth is 0 since this has significant overhead
+                    .span_alloc(Some($name), _function_name, file!(), line!(), 0),
+            )
+        } else {
+            None
+        }
     };
+    ($name:expr, $data:expr) => {{
+        let _tracy_span = $crate::__tracy_scope!($name);
+        if let Some(_tracy_span) = &_tracy_span {
+            _tracy_span.emit_text($data);
+        }
+        _tracy_span
+    }};
 }
 
+// Integer fields become zone values. Zone values have no name and are unsigned, so negative
+// integers and the other fields are added as a line of `key = value` text instead.
+#[doc(hidden)]
 #[macro_export]
-macro_rules! function_scope {
+macro_rules! __tracy_field {
+    ($span:expr, $key:ident, $value:expr) => {
+        match $crate::AsFieldValue::as_field_value(&$value) {
+            $crate::FieldValue::U64(_value) => $span.emit_value(_value),
+            $crate::FieldValue::I64(_value) if _value >= 0 => $span.emit_value(_value as u64),
+            _value => $span.emit_text(&format!("{} = {}", stringify!($key), _value)),
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracy_function_scope {
+    (color = $color:expr) => {{
+        let _tracy_span = $crate::__tracy_function_scope!();
+        if let Some(_tracy_span) = &_tracy_span {
+            _tracy_span.emit_color($color);
+        }
+        _tracy_span
+    }};
+    ($data:expr, color = $color:expr) => {{
+        let _tracy_span = $crate::__tracy_function_scope!($data);
+        if let Some(_tracy_span) = &_tracy_span {
+            _tracy_span.emit_color($color);
+        }
+        _tracy_span
+    }};
     () => {
-        let _tracy_span = $crate::tracy_client::span!();
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            Some($crate::tracy_client::span!())
+        } else {
+            None
+        }
     };
     ($data:expr) => {
-        let _location = $crate::tracy_client::span_location!();
-        let _tracy_span = $crate::tracy_client::Client::running()
-            .expect("function_scope! without a running tracy_client::Client")
-            .span(_location, 0);
-        _tracy_span.emit_text($data);
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            let _location = $crate::tracy_client::span_location!();
+            let _tracy_span = $crate::tracy_client::Client::running()
+                .expect("function_scope! without a running tracy_client::Client")
+                .span(_location, 0);
+            _tracy_span.emit_text($data);
+            Some(_tracy_span)
+        } else {
+            None
+        }
     };
 }
 
-/// Registers a thread with the profiler API(s). This is usually setting a name for the thread.
-/// Two variants:
-///  - register_thread!() - Tries to get the name of the thread, or an ID if no name is set
-///  - register_thread!(name: &str) - Registers the thread using the given name
+#[doc(hidden)]
 #[macro_export]
-macro_rules! register_thread {
+macro_rules! __tracy_register_thread {
     () => {
-        let thread_name = std::thread::current()
-            .name()
-            .map(|x| x.to_string())
-            .unwrap_or_else(|| format!("Thread {:?}", std::thread::current().id()));
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            let thread_name = std::thread::current()
+                .name()
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| format!("Thread {:?}", std::thread::current().id()));
 
-        $crate::register_thread!(&thread_name);
+            $crate::__tracy_register_thread!(&thread_name);
+        }
     };
     ($name:expr) => {
-        $crate::tracy_client::Client::running()
-            .expect("register_thread! without a running tracy_client::Client")
-            .set_thread_name($name);
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            $crate::tracy_client::Client::running()
+                .expect("register_thread! without a running tracy_client::Client")
+                .set_thread_name($name);
+        }
     };
 }
 
-/// Finishes the frame. This isn't strictly necessary for some kinds of applications but a pretty
-/// normal thing to track in games.
+#[doc(hidden)]
 #[macro_export]
-macro_rules! finish_frame {
+macro_rules! __tracy_finish_frame {
     () => {
-        $crate::tracy_client::Client::running()
-            .expect("finish_frame! without a running tracy_client::Client")
-            .frame_mark();
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            $crate::tracy_client::Client::running()
+                .expect("finish_frame! without a running tracy_client::Client")
+                .frame_mark();
+        }
+    };
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            let _client = $crate::tracy_client::Client::running()
+                .expect("finish_frame! without a running tracy_client::Client");
+            static _NAME: $crate::TracyFrameName = $crate::TracyFrameName::new();
+            // SAFETY: the name is null-terminated and static, and the client is running
+            unsafe {
+                $crate::tracy_client::sys::___tracy_emit_frame_mark(
+                    _NAME.get(concat!($name, "\0")),
+                );
+            }
+        }
+    };
+}
+
+// tracy_client only exposes discontinuous frames as a guard, but frame_start! and frame_end! may be
+// called from different scopes, so these call into tracy directly. Like finish_frame!, they pass
+// the name through TracyFrameName so that every call site uses the same address for it.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracy_frame_start {
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            let _client = $crate::tracy_client::Client::running()
+                .expect("frame_start! without a running tracy_client::Client");
+            static _NAME: $crate::TracyFrameName = $crate::TracyFrameName::new();
+            // SAFETY: the name is null-terminated and static, and the client is running
+            unsafe {
+                $crate::tracy_client::sys::___tracy_emit_frame_mark_start(
+                    _NAME.get(concat!($name, "\0")),
+                );
+            }
+        }
     };
 }
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracy_frame_end {
+    ($name:literal) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            let _client = $crate::tracy_client::Client::running()
+                .expect("frame_end! without a running tracy_client::Client");
+            static _NAME: $crate::TracyFrameName = $crate::TracyFrameName::new();
+            // SAFETY: the name is null-terminated and static, and the client is running
+            unsafe {
+                $crate::tracy_client::sys::___tracy_emit_frame_mark_end(
+                    _NAME.get(concat!($name, "\0")),
+                );
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracy_plot {
+    ($name:literal, $value:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            $crate::tracy_client::Client::running()
+                .expect("plot! without a running tracy_client::Client")
+                .plot($crate::tracy_client::plot_name!($name), $value as f64);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracy_message {
+    ($text:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            $crate::tracy_client::Client::running()
+                .expect("message! without a running tracy_client::Client")
+                // Note: callstack_depth is 0 since this has significant overhead
+                .message($text, 0);
+        }
+    };
+    ($text:expr, $color:expr) => {
+        if $crate::is_backend_enabled($crate::Backend::Tracy) {
+            let _color: u32 = $color;
+            $crate::tracy_client::Client::running()
+                .expect("message! without a running tracy_client::Client")
+                // tracy expects RGBA
+                .color_message($text, _color << 8 | 0xFF, 0);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tracy_marker {
+    // tracy has no instant events, so this is a zero-length scope on the current thread
+    ($scope:ident; $($args:tt)*) => {
+        let _ = $crate::__tracy_scope!($($args)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::TracyFrameName;
+    use std::boxed::Box;
+    use std::string::String;
+
+    #[test]
+    fn frame_names_share_an_address() {
+        let first: &'static str = Box::leak(String::from("Loading\0").into_boxed_str());
+        let second: &'static str = Box::leak(String::from("Loading\0").into_boxed_str());
+        assert_ne!(first.as_ptr(), second.as_ptr());
+
+        let start = TracyFrameName::new();
+        let end = TracyFrameName::new();
+        assert_eq!(start.get(first), first.as_ptr().cast());
+        assert_eq!(end.get(second), first.as_ptr().cast());
+        // Cached after the first lookup
+        assert_eq!(end.get(second), first.as_ptr().cast());
+    }
+}
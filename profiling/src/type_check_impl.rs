@@ -1,33 +1,118 @@
 // This backend is intended to force type checking
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! scope {
-    ($name:expr) => {
+macro_rules! __type_check_scope {
+    (name = $fmt:literal; $name:expr) => {{
+        let _: core::fmt::Arguments = $name;
+    }};
+    ($name:expr, color = $color:expr) => {{
         let _: &str = $name;
-    };
-    ($name:expr, $data:expr) => {
+        let _: u32 = $color;
+    }};
+    ($name:expr, $data:expr, color = $color:expr) => {{
         let _: &str = $name;
         let _: &str = $data;
-    };
+        let _: u32 = $color;
+    }};
+    ($name:expr, $($key:ident = $value:expr),+ $(,)?) => {{
+        let _: &str = $name;
+        $(let _ = $crate::AsFieldValue::as_field_value(&$value);)+
+    }};
+    ($name:expr, $fmt:literal, $($arg:tt)+) => {{
+        let _: &str = $name;
+        let _ = format_args!($fmt, $($arg)+);
+    }};
+    ($name:expr) => {{
+        let _: &str = $name;
+    }};
+    ($name:expr, $data:expr) => {{
+        let _: &str = $name;
+        let _: &str = $data;
+    }};
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! function_scope {
-    () => {};
-    ($data:expr) => {
+macro_rules! __type_check_function_scope {
+    (color = $color:expr) => {{
+        let _: u32 = $color;
+    }};
+    ($data:expr, color = $color:expr) => {{
         let _: &str = $data;
-    };
+        let _: u32 = $color;
+    }};
+    () => {{}};
+    ($data:expr) => {{
+        let _: &str = $data;
+    }};
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! register_thread {
+macro_rules! __type_check_register_thread {
     () => {};
     ($name:expr) => {
         let _: &str = $name;
     };
 }
 
+#[doc(hidden)]
 #[macro_export]
-macro_rules! finish_frame {
+macro_rules! __type_check_finish_frame {
     () => {};
+    ($name:literal) => {
+        let _: &str = $name;
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __type_check_frame_start {
+    ($name:literal) => {
+        let _: &str = $name;
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __type_check_frame_end {
+    ($name:literal) => {
+        let _: &str = $name;
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __type_check_plot {
+    ($name:literal, $value:expr) => {
+        let _: &str = $name;
+        let _: f64 = $value as f64;
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __type_check_message {
+    ($text:expr) => {
+        let _: &str = $text;
+    };
+    ($text:expr, $color:expr) => {
+        let _: &str = $text;
+        let _: u32 = $color;
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __type_check_marker {
+    ($scope:ident; $name:expr) => {
+        let _: &str = $name;
+        let _: $crate::MarkerScope = $crate::MarkerScope::$scope;
+    };
+    ($scope:ident; $name:expr, $data:expr) => {
+        let _: &str = $name;
+        let _: &str = $data;
+        let _: $crate::MarkerScope = $crate::MarkerScope::$scope;
+    };
 }